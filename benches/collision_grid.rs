@@ -0,0 +1,42 @@
+use std::time::{Duration, Instant};
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use road_intersection::{Direction, Turn, World, WINDOW_HEIGHT, WINDOW_WIDTH};
+
+const CROWD_SIZE: usize = 500;
+const SECOND_OF_TICKS: u32 = 60;
+const SECOND_BUDGET: Duration = Duration::from_millis(500); // Half of real time for a simulated second
+
+// Seeded world with CROWD_SIZE vehicles scattered over the map, far more than spawning allows
+fn crowd() -> World {
+    let mut world = World::with_seed(42);
+    for i in 0..CROWD_SIZE {
+        let dir = Direction::APPROACHES[i % Direction::APPROACHES.len()];
+        let x = (i as u32 * 37) % WINDOW_WIDTH;
+        let y = (i as u32 * 53) % WINDOW_HEIGHT;
+        world.add_vehicle(dir, Turn::Straight, (x as f32, y as f32), 0);
+    }
+    world
+}
+
+// The proximity check must stay well below quadratic cost for the crowd to run in real time
+fn check_crowd_budget() {
+    let mut world = crowd();
+    let start = Instant::now();
+    for _ in 0..SECOND_OF_TICKS {
+        world.update();
+    }
+    let elapsed = start.elapsed();
+    assert!(elapsed < SECOND_BUDGET, "{} vehicles took {:?} for one simulated second", CROWD_SIZE, elapsed);
+}
+
+fn bench_collision_grid(c: &mut Criterion) {
+    check_crowd_budget();
+
+    c.bench_function("crowd_update", |b| {
+        b.iter_batched_ref(crowd, |world| world.update(), BatchSize::LargeInput)
+    });
+}
+
+criterion_group!(benches, bench_collision_grid);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use road_intersection::{Direction, PositionMode, World};
+
+const SCENE_SPAWNS: u32 = 300;
+
+// Seeded world that has spawned SCENE_SPAWNS vehicles, round-robin over the approaches
+fn scene(mode: PositionMode) -> World {
+    let mut world = World::with_seed(42);
+    world.position_mode = mode;
+    let mut spawned = 0;
+    while spawned < SCENE_SPAWNS {
+        for dir in Direction::APPROACHES {
+            if spawned < SCENE_SPAWNS && world.spawn_vehicle(dir).is_some() {
+                spawned += 1;
+            }
+        }
+        world.update();
+    }
+    world
+}
+
+// A slow vehicle on a near-diagonal segment: integer stepping truncates the speed to zero
+// and never moves, float stepping keeps making progress
+fn check_diagonal_stall() {
+    let moved = |mode: PositionMode| {
+        let mut world = World::with_seed(42);
+        let id = world.spawn_vehicle(Direction::West).expect("empty world accepts a spawn");
+        let v = world.vehicles.iter_mut().find(|v| v.id == id).unwrap();
+        let start = (v.x, v.y);
+        v.path[1] = (start.0 + 100, start.1 + 10);
+        v.speed = 0.5;
+        for _ in 0..100 {
+            v.advance(mode);
+        }
+        (v.x, v.y) != start
+    };
+    assert!(!moved(PositionMode::Integer), "integer mode was expected to stall");
+    assert!(moved(PositionMode::Float), "float mode must not stall");
+}
+
+fn bench_position_modes(c: &mut Criterion) {
+    check_diagonal_stall();
+
+    let mut group = c.benchmark_group("world_update");
+    for (name, mode) in [("integer", PositionMode::Integer), ("float", PositionMode::Float)] {
+        group.bench_function(name, |b| {
+            b.iter_batched_ref(|| scene(mode), |world| world.update(), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_position_modes);
+criterion_main!(benches);
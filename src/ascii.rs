@@ -0,0 +1,133 @@
+use crate::traffic_light::SignalColor;
+use crate::{Direction, VEHICLE_SIZE, World};
+
+// Text-mode view of the world for terminals without a display
+
+pub const ASCII_CELL_WIDTH: u32 = 10; // Screen pixels per character column
+pub const ASCII_CELL_HEIGHT: u32 = 20; // Screen pixels per character row
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Renderer {
+    Window,
+    Ascii,
+}
+
+// --headless always selects the ASCII renderer; otherwise a window needs a display
+pub fn choose_renderer(headless: bool, display_available: bool) -> Result<Renderer, String> {
+    if headless {
+        Ok(Renderer::Ascii)
+    } else if display_available {
+        Ok(Renderer::Window)
+    } else {
+        Err("no display found; try headless mode with --headless".to_string())
+    }
+}
+
+// Character for a vehicle travelling with the given heading (screen radians, 0 is east)
+pub fn heading_glyph(heading: f32) -> char {
+    let (sin, cos) = heading.sin_cos();
+    if cos.abs() >= sin.abs() {
+        if cos >= 0.0 { '>' } else { '<' }
+    } else if sin >= 0.0 {
+        'v'
+    } else {
+        '^'
+    }
+}
+
+// One frame as lines of text: roads '.', the box '+', lights 'G'/'Y'/'R', vehicles by travel direction
+pub fn render(world: &World) -> String {
+    let config = world.config();
+    let cols = (config.window_width / ASCII_CELL_WIDTH) as usize;
+    let rows = (config.window_height / ASCII_CELL_HEIGHT) as usize;
+    let (road_x, road_y, road_width) = (config.road_x(), config.road_y(), config.road_width);
+    let mut grid = vec![vec![' '; cols]; rows];
+
+    for (row, line) in grid.iter_mut().enumerate() {
+        let y = row as u32 * ASCII_CELL_HEIGHT + ASCII_CELL_HEIGHT / 2;
+        for (col, cell) in line.iter_mut().enumerate() {
+            let x = col as u32 * ASCII_CELL_WIDTH + ASCII_CELL_WIDTH / 2;
+            let on_vertical = (road_x..road_x + road_width).contains(&x);
+            let on_horizontal = (road_y..road_y + road_width).contains(&y);
+            *cell = match (on_vertical, on_horizontal) {
+                (true, true) => '+',
+                (true, false) | (false, true) => '.',
+                _ => ' ',
+            };
+        }
+    }
+
+    // Lights sit just outside the box corner on each approach's curb side
+    let (left, top) = (config.intersection_x_start(), config.intersection_y_start());
+    let (right, bottom) = (config.intersection_x_end(), config.intersection_y_end());
+    for (dir, color) in world.controller.signal_states() {
+        let (x, y) = match (dir, config.drive_on_left) {
+            (Direction::North, false) | (Direction::West, true) => (left - ASCII_CELL_WIDTH, top - ASCII_CELL_HEIGHT),
+            (Direction::South, false) | (Direction::East, true) => (right, bottom),
+            (Direction::East, false) | (Direction::North, true) => (right, top - ASCII_CELL_HEIGHT),
+            (Direction::West, false) | (Direction::South, true) => (left - ASCII_CELL_WIDTH, bottom),
+            (Direction::AllRed, _) => continue,
+        };
+        let glyph = match color {
+            SignalColor::Green => 'G',
+            SignalColor::Yellow => 'Y',
+            SignalColor::Red => 'R',
+        };
+        set_cell(&mut grid, x as i32, y as i32, glyph);
+    }
+
+    let half = VEHICLE_SIZE as i32 / 2;
+    for v in world.vehicles.iter().filter(|v| !v.passed) {
+        set_cell(&mut grid, v.x + half, v.y + half, heading_glyph(v.heading()));
+    }
+
+    let mut out = String::with_capacity(rows * (cols + 1));
+    for line in grid {
+        out.extend(line);
+        out.push('\n');
+    }
+    out
+}
+
+fn set_cell(grid: &mut [Vec<char>], x: i32, y: i32, glyph: char) {
+    if x < 0 || y < 0 {
+        return;
+    }
+    let (col, row) = ((x as u32 / ASCII_CELL_WIDTH) as usize, (y as u32 / ASCII_CELL_HEIGHT) as usize);
+    if let Some(cell) = grid.get_mut(row).and_then(|line| line.get_mut(col)) {
+        *cell = glyph;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Markings, like the rest of a frame, are drawn from a shared borrow of the world, so showing
+    // or hiding them cannot change where vehicles go
+    #[test]
+    fn drawing_frames_leaves_the_run_unchanged() {
+        let mut drawn = World::with_seed(4);
+        let mut undrawn = World::with_seed(4);
+        for tick in 0..600 {
+            if tick % 15 == 0 {
+                drawn.spawn_random();
+                undrawn.spawn_random();
+            }
+            drawn.update();
+            undrawn.update();
+            render(&drawn);
+            let positions = |world: &World| world.vehicles.iter().map(|v| (v.id, v.x, v.y)).collect::<Vec<_>>();
+            assert_eq!(positions(&drawn), positions(&undrawn), "tick {}", tick);
+        }
+    }
+
+    #[test]
+    fn renderer_follows_flag_then_display() {
+        assert_eq!(choose_renderer(true, true), Ok(Renderer::Ascii));
+        assert_eq!(choose_renderer(true, false), Ok(Renderer::Ascii));
+        assert_eq!(choose_renderer(false, true), Ok(Renderer::Window));
+        let err = choose_renderer(false, false).unwrap_err();
+        assert!(err.contains("--headless"), "{}", err);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+pub const MAX_ZOOM: f32 = 8.0;
+pub const ZOOM_STEP: f32 = 1.25; // Zoom factor per key press or wheel notch
+pub const PAN_STEP: f32 = 40.0; // Screen pixels per pan key press
+
+// View transform from world coordinates to the window. It only affects rendering: simulation
+// coordinates stay in world space. Zooming out stops at the whole world, which fills the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub zoom: f32,
+    pub origin: (f32, f32), // World point shown at the top-left corner of the window
+    pub world_size: (u32, u32), // Same as the window size, so the whole world fits at zoom 1
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(WINDOW_WIDTH, WINDOW_HEIGHT)
+    }
+}
+
+impl Camera {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            zoom: 1.0,
+            origin: (0.0, 0.0),
+            world_size: (width, height),
+        }
+    }
+
+    pub fn to_screen(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        (
+            ((x as f32 - self.origin.0) * self.zoom).round() as i32,
+            ((y as f32 - self.origin.1) * self.zoom).round() as i32,
+        )
+    }
+
+    pub fn to_world(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        (
+            (x as f32 / self.zoom + self.origin.0).floor() as i32,
+            (y as f32 / self.zoom + self.origin.1).floor() as i32,
+        )
+    }
+
+    // Screen rectangle (x, y, width, height) of a world rectangle. Both corners are mapped, so
+    // rectangles that touch in the world still touch on screen.
+    pub fn to_screen_rect(&self, x: i32, y: i32, width: u32, height: u32) -> (i32, i32, u32, u32) {
+        let (left, top) = self.to_screen((x, y));
+        let (right, bottom) = self.to_screen((x + width as i32, y + height as i32));
+        (left, top, (right - left).max(1) as u32, (bottom - top).max(1) as u32)
+    }
+
+    // Multiplies the zoom by factor while keeping the world point under `anchor` (a screen point) in place
+    pub fn zoom_at(&mut self, factor: f32, anchor: (i32, i32)) {
+        let zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+        let world = (
+            anchor.0 as f32 / self.zoom + self.origin.0,
+            anchor.1 as f32 / self.zoom + self.origin.1,
+        );
+        self.zoom = zoom;
+        self.origin = (world.0 - anchor.0 as f32 / zoom, world.1 - anchor.1 as f32 / zoom);
+        self.clamp();
+    }
+
+    // Moves the view by a distance in screen pixels
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.origin.0 += dx / self.zoom;
+        self.origin.1 += dy / self.zoom;
+        self.clamp();
+    }
+
+    // Keeps the view inside the world
+    fn clamp(&mut self) {
+        let max_x = self.world_size.0 as f32 * (1.0 - 1.0 / self.zoom);
+        let max_y = self.world_size.1 as f32 * (1.0 - 1.0 / self.zoom);
+        self.origin = (self.origin.0.clamp(0.0, max_x), self.origin.1.clamp(0.0, max_y));
+    }
+}
@@ -0,0 +1,185 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::TICK_DURATION;
+
+// One captured frame as tightly packed RGBA pixels, stamped with the simulation tick it shows
+pub struct Frame {
+    pub width: u16,
+    pub height: u16,
+    pub tick: u64,
+    pub rgba: Vec<u8>,
+}
+
+// Collects frames for ticks in start_tick..end_tick. Frames are keyed by simulation tick rather
+// than render frame, so the GIF plays at simulation speed even when several ticks run per frame.
+pub struct GifCapture {
+    pub start_tick: u64,
+    pub end_tick: u64,
+    pub downscale: u16, // Frames are shrunk by this factor on both axes, 1 keeps full resolution
+    pub tick_interval: u64, // Only every this many ticks is captured; frame delays stretch to match
+    frames: Vec<Frame>,
+}
+
+impl GifCapture {
+    pub fn new(start_tick: u64, end_tick: u64) -> Self {
+        Self {
+            start_tick,
+            end_tick,
+            downscale: 1,
+            tick_interval: 1,
+            frames: Vec::new(),
+        }
+    }
+
+    // Smaller files for long recordings: shrink each frame by downscale and keep one tick in tick_interval
+    pub fn with_sampling(mut self, downscale: u16, tick_interval: u64) -> Self {
+        self.downscale = downscale.max(1);
+        self.tick_interval = tick_interval.max(1);
+        self
+    }
+
+    pub fn wants(&self, tick: u64) -> bool {
+        self.is_recording(tick) && (tick - self.start_tick).is_multiple_of(self.tick_interval)
+    }
+
+    // Ends the recording early; frames up to tick are kept
+    pub fn stop(&mut self, tick: u64) {
+        self.end_tick = self.end_tick.min(tick);
+    }
+
+    // Inside the recorded range, whether or not this tick is sampled
+    pub fn is_recording(&self, tick: u64) -> bool {
+        (self.start_tick..self.end_tick).contains(&tick)
+    }
+
+    pub fn is_finished(&self, tick: u64) -> bool {
+        tick >= self.end_tick
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        self.frames.push(frame.downscaled(self.downscale));
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        encode_gif(&self.frames, BufWriter::new(file))
+    }
+}
+
+// Delay before the next frame in GIF units (hundredths of a second), at least 1
+fn frame_delay(ticks: u64) -> u16 {
+    let centis = (ticks as u128 * TICK_DURATION.as_millis()).div_ceil(10);
+    centis.clamp(1, u16::MAX as u128) as u16
+}
+
+// Encodes frames as a looping animated GIF; every frame must match the first one's size
+pub fn encode_gif<W: Write>(frames: &[Frame], writer: W) -> Result<(), String> {
+    let Some(first) = frames.first() else {
+        return Err("no frames to encode".to_string());
+    };
+    let mut encoder = gif::Encoder::new(writer, first.width, first.height, &[]).map_err(|e| e.to_string())?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+
+    for (i, frame) in frames.iter().enumerate() {
+        if (frame.width, frame.height) != (first.width, first.height) {
+            return Err(format!("frame {} is {}x{}, expected {}x{}", i, frame.width, frame.height, first.width, first.height));
+        }
+        if frame.rgba.len() != frame.width as usize * frame.height as usize * 4 {
+            return Err(format!("frame {} has {} bytes of pixel data", i, frame.rgba.len()));
+        }
+        let ticks_shown = frames.get(i + 1).map_or(1, |next| next.tick.saturating_sub(frame.tick));
+        let mut rgba = frame.rgba.clone();
+        let mut gif_frame = gif::Frame::from_rgba_speed(frame.width, frame.height, &mut rgba, 10);
+        gif_frame.delay = frame_delay(ticks_shown);
+        encoder.write_frame(&gif_frame).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+impl Frame {
+    // Averages each factor x factor block into one pixel; edge pixels that do not fill a block are dropped
+    pub fn downscaled(self, factor: u16) -> Frame {
+        if factor <= 1 {
+            return self;
+        }
+        let (width, height) = (self.width / factor, self.height / factor);
+        let f = factor as usize;
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                let mut sum = [0u32; 4];
+                for dy in 0..f {
+                    let row = (y * f + dy) * self.width as usize;
+                    for dx in 0..f {
+                        let i = (row + x * f + dx) * 4;
+                        for (c, total) in sum.iter_mut().enumerate() {
+                            *total += self.rgba[i + c] as u32;
+                        }
+                    }
+                }
+                rgba.extend(sum.map(|total| (total / (f * f) as u32) as u8));
+            }
+        }
+        Frame { width, height, tick: self.tick, rgba }
+    }
+
+    // Saves this frame alone as a PNG, e.g. a screenshot to attach to a bug report
+    pub fn write_png(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        encode_png(self, BufWriter::new(file))
+    }
+}
+
+// Encodes one frame as an 8-bit RGBA PNG
+pub fn encode_png<W: Write>(frame: &Frame, writer: W) -> Result<(), String> {
+    if frame.rgba.len() != frame.width as usize * frame.height as usize * 4 {
+        return Err(format!("frame has {} bytes of pixel data", frame.rgba.len()));
+    }
+    let mut encoder = png::Encoder::new(writer, frame.width as u32, frame.height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(&frame.rgba).map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(tick: u64, width: u16, height: u16, color: [u8; 4]) -> Frame {
+        Frame { width, height, tick, rgba: color.repeat(width as usize * height as usize) }
+    }
+
+    #[test]
+    fn encodes_synthetic_frames_with_tick_based_delays() {
+        let frames = [
+            solid(0, 4, 3, [255, 0, 0, 255]),
+            solid(2, 4, 3, [0, 255, 0, 255]),
+            solid(3, 4, 3, [0, 0, 255, 255]),
+        ];
+        let mut bytes = Vec::new();
+        encode_gif(&frames, &mut bytes).unwrap();
+
+        let mut decoder = gif::DecodeOptions::new().read_info(bytes.as_slice()).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (4, 3));
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            delays.push(frame.delay);
+        }
+        // 2 ticks of 16 ms, then 1 tick each, rounded up to hundredths of a second
+        assert_eq!(delays, [4, 2, 2]);
+    }
+
+    #[test]
+    fn rejects_frames_of_a_different_size() {
+        let frames = [solid(0, 4, 3, [0; 4]), solid(1, 2, 2, [0; 4])];
+        assert!(encode_gif(&frames, Vec::new()).is_err());
+        assert!(encode_gif(&[], Vec::new()).is_err());
+    }
+}
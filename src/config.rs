@@ -0,0 +1,146 @@
+use crate::vehicle;
+use crate::{ROAD_WIDTH, Turn, VEHICLE_SIZE, WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// Road needed on each arm outside the box for vehicles to spawn, brake and stop before the line
+pub const MIN_ARM_LENGTH: u32 = 4 * VEHICLE_SIZE;
+
+// Window size and road geometry. The WINDOW_* / ROAD_* / *_LANE_* constants describe the default
+// layout; everything that depends on the layout reads it from here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Config {
+    pub window_width: u32,
+    pub window_height: u32,
+    pub road_width: u32, // Both roads; the intersection box is road_width square in the window center
+    pub drive_on_left: bool, // Keep-left traffic: the whole intersection operates mirror-image
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            window_width: WINDOW_WIDTH,
+            window_height: WINDOW_HEIGHT,
+            road_width: ROAD_WIDTH,
+            drive_on_left: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn with_window(mut self, width: u32, height: u32) -> Self {
+        self.window_width = width;
+        self.window_height = height;
+        self
+    }
+
+    pub fn with_road_width(mut self, road_width: u32) -> Self {
+        self.road_width = road_width;
+        self
+    }
+
+    pub fn with_drive_on_left(mut self, drive_on_left: bool) -> Self {
+        self.drive_on_left = drive_on_left;
+        self
+    }
+
+    // Err when the lanes would not fit on the road or the arms are too short to queue on
+    pub fn validate(&self) -> Result<(), String> {
+        if !vehicle::lanes_fit(self) {
+            return Err(format!("road width {} is too narrow for two lanes in each direction", self.road_width));
+        }
+        let min_width = self.road_width + 2 * MIN_ARM_LENGTH;
+        if self.window_width < min_width || self.window_height < min_width {
+            return Err(format!(
+                "window {}x{} is too small for a road width of {}; both sides need at least {}",
+                self.window_width, self.window_height, self.road_width, min_width
+            ));
+        }
+        Ok(())
+    }
+
+    // Left edge of the north-south road
+    pub fn road_x(&self) -> u32 {
+        (self.window_width.saturating_sub(self.road_width)) / 2
+    }
+
+    // Top edge of the east-west road
+    pub fn road_y(&self) -> u32 {
+        (self.window_height.saturating_sub(self.road_width)) / 2
+    }
+
+    pub fn intersection_x_start(&self) -> u32 {
+        self.road_x()
+    }
+
+    pub fn intersection_x_end(&self) -> u32 {
+        self.road_x() + self.road_width
+    }
+
+    pub fn intersection_y_start(&self) -> u32 {
+        self.road_y()
+    }
+
+    pub fn intersection_y_end(&self) -> u32 {
+        self.road_y() + self.road_width
+    }
+
+    // Centre of the half of the road a direction drives on: the right half in the direction of
+    // travel, or the left half when driving on the left
+    pub fn northbound_lane_x(&self) -> i32 {
+        if self.drive_on_left { self.west_half_x() } else { self.east_half_x() }
+    }
+
+    pub fn southbound_lane_x(&self) -> i32 {
+        if self.drive_on_left { self.east_half_x() } else { self.west_half_x() }
+    }
+
+    pub fn eastbound_lane_y(&self) -> i32 {
+        if self.drive_on_left { self.north_half_y() } else { self.south_half_y() }
+    }
+
+    pub fn westbound_lane_y(&self) -> i32 {
+        if self.drive_on_left { self.south_half_y() } else { self.north_half_y() }
+    }
+
+    fn east_half_x(&self) -> i32 {
+        (self.road_x() + self.road_width / 2 + self.road_x() + self.road_width) as i32 / 2
+    }
+
+    fn west_half_x(&self) -> i32 {
+        (self.road_x() + self.road_width / 2 + self.road_x()) as i32 / 2
+    }
+
+    fn south_half_y(&self) -> i32 {
+        (self.road_y() + self.road_width / 2 + self.road_y() + self.road_width) as i32 / 2
+    }
+
+    fn north_half_y(&self) -> i32 {
+        (self.road_y() + self.road_width / 2 + self.road_y()) as i32 / 2
+    }
+
+    // Distance of each of an approach's two lanes from its *_lane_x / *_lane_y line
+    pub fn turn_lane_shift(&self) -> i32 {
+        self.road_width as i32 / 8
+    }
+
+    // 1 when traffic keeps right, -1 when it keeps left. Lateral offsets written for keep-right
+    // traffic are multiplied by this to mirror them.
+    pub fn traffic_side(&self) -> i32 {
+        if self.drive_on_left { -1 } else { 1 }
+    }
+
+    // The turn that crosses the oncoming lanes, which uses the inner lane and yields on a shared green
+    pub fn crossing_turn(&self) -> Turn {
+        if self.drive_on_left { Turn::Right } else { Turn::Left }
+    }
+
+    // The turn that stays on the near side of the box, e.g. the one allowed on red
+    pub fn curb_turn(&self) -> Turn {
+        if self.drive_on_left { Turn::Left } else { Turn::Right }
+    }
+
+    // Window center, where the two roads cross
+    pub fn center(&self) -> (i32, i32) {
+        (self.window_width as i32 / 2, self.window_height as i32 / 2)
+    }
+}
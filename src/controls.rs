@@ -0,0 +1,71 @@
+use crate::Direction;
+use crate::config::Config;
+
+// Arrow keys, kept separate from SDL keycodes so the mapping can be checked headless
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrowKey {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeymapMode {
+    TravelHeading, // Key is the direction of travel: Up spawns a vehicle driving up from the bottom edge
+    ScreenEdge,    // Key is the edge the vehicle enters from: Up spawns a vehicle at the top edge
+}
+
+impl KeymapMode {
+    pub fn toggled(self) -> Self {
+        match self {
+            KeymapMode::TravelHeading => KeymapMode::ScreenEdge,
+            KeymapMode::ScreenEdge => KeymapMode::TravelHeading,
+        }
+    }
+}
+
+// Approach to spawn for an arrow key press
+pub fn spawn_direction(key: ArrowKey, mode: KeymapMode) -> Direction {
+    match (mode, key) {
+        (KeymapMode::TravelHeading, ArrowKey::Up) => Direction::South,
+        (KeymapMode::TravelHeading, ArrowKey::Down) => Direction::North,
+        (KeymapMode::TravelHeading, ArrowKey::Left) => Direction::East,
+        (KeymapMode::TravelHeading, ArrowKey::Right) => Direction::West,
+        (KeymapMode::ScreenEdge, ArrowKey::Up) => Direction::North,
+        (KeymapMode::ScreenEdge, ArrowKey::Down) => Direction::South,
+        (KeymapMode::ScreenEdge, ArrowKey::Left) => Direction::West,
+        (KeymapMode::ScreenEdge, ArrowKey::Right) => Direction::East,
+    }
+}
+
+// Approach whose arm of the cross is nearest to a click: the top arm spawns a vehicle entering
+// from the top edge, i.e. Direction::North, and so on around the window center
+pub fn click_direction(config: &Config, x: i32, y: i32) -> Direction {
+    let (center_x, center_y) = config.center();
+    let dx = x - center_x;
+    let dy = y - center_y;
+    if dy.abs() >= dx.abs() {
+        if dy < 0 { Direction::North } else { Direction::South }
+    } else if dx < 0 {
+        Direction::West
+    } else {
+        Direction::East
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrow_keys_map_to_approaches_in_both_modes() {
+        let keys = [ArrowKey::Up, ArrowKey::Down, ArrowKey::Left, ArrowKey::Right];
+        let travel = keys.map(|key| spawn_direction(key, KeymapMode::TravelHeading));
+        let edge = keys.map(|key| spawn_direction(key, KeymapMode::ScreenEdge));
+        assert_eq!(travel, [Direction::South, Direction::North, Direction::East, Direction::West]);
+        assert_eq!(edge, [Direction::North, Direction::South, Direction::West, Direction::East]);
+        assert_eq!(KeymapMode::TravelHeading.toggled(), KeymapMode::ScreenEdge);
+        assert_eq!(KeymapMode::ScreenEdge.toggled(), KeymapMode::TravelHeading);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::vehicle::VehicleKind;
+use crate::{Direction, Turn};
+use rand::Rng;
+
+// Relative likelihood of each turn for vehicles entering from one approach
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TurnWeights {
+    pub left: u32,
+    pub right: u32,
+    pub straight: u32,
+}
+
+impl TurnWeights {
+    pub const UNIFORM: TurnWeights = TurnWeights { left: 1, right: 1, straight: 1 };
+}
+
+// Turn mix per approach, consulted whenever a vehicle is spawned with a random turn, and the
+// mix of vehicle kinds for spawns that do not pick one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpawnDistribution {
+    turn_weights: [TurnWeights; 4], // Indexed like Direction::APPROACHES
+    kind_weights: [u32; 3], // Indexed like VehicleKind::ALL
+}
+
+impl Default for SpawnDistribution {
+    fn default() -> Self {
+        Self::uniform()
+    }
+}
+
+impl SpawnDistribution {
+    pub fn uniform() -> Self {
+        Self {
+            turn_weights: [TurnWeights::UNIFORM; 4],
+            kind_weights: [1, 0, 0],
+        }
+    }
+
+    pub fn turn_weights(&self, dir: Direction) -> TurnWeights {
+        dir.approach_index().map_or(TurnWeights::UNIFORM, |i| self.turn_weights[i])
+    }
+
+    pub fn set_turn_weights(&mut self, dir: Direction, weights: TurnWeights) {
+        if let Some(i) = dir.approach_index() {
+            self.turn_weights[i] = weights;
+        }
+    }
+
+    pub fn kind_weights(&self) -> [u32; 3] {
+        self.kind_weights
+    }
+
+    // Relative likelihood of a car, truck and motorcycle; all zero spawns only cars
+    pub fn set_kind_weights(&mut self, weights: [u32; 3]) {
+        self.kind_weights = weights;
+    }
+
+    // Only draws from rng when more than one kind can come up, so runs with the default
+    // all-car mix replay the same as before kinds existed
+    pub fn sample_kind<R: Rng>(&self, rng: &mut R) -> VehicleKind {
+        let mut weighted = VehicleKind::ALL.into_iter().zip(self.kind_weights).filter(|&(_, w)| w > 0);
+        let only = weighted.next().map(|(kind, _)| kind);
+        if weighted.next().is_none() {
+            return only.unwrap_or(VehicleKind::Car);
+        }
+        let mut pick = rng.gen_range(0..self.kind_weights.iter().sum::<u32>());
+        for (kind, weight) in VehicleKind::ALL.into_iter().zip(self.kind_weights) {
+            if pick < weight {
+                return kind;
+            }
+            pick -= weight;
+        }
+        VehicleKind::Car
+    }
+
+    pub fn sample_turn<R: Rng>(&self, dir: Direction, rng: &mut R) -> Turn {
+        let w = self.turn_weights(dir);
+        let total = w.left + w.right + w.straight;
+        if total == 0 {
+            return Turn::Straight;
+        }
+        let pick = rng.gen_range(0..total);
+        if pick < w.left {
+            Turn::Left
+        } else if pick < w.left + w.right {
+            Turn::Right
+        } else {
+            Turn::Straight
+        }
+    }
+}
+
+// One line of a demand profile: arrival rate (vehicles per tick) at an approach
+// for ticks in start_tick..end_tick
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemandEntry {
+    pub start_tick: u64,
+    pub end_tick: u64,
+    pub dir: Direction,
+    pub rate: f64,
+}
+
+// Time-varying arrival rates, loaded from CSV lines of `start_tick,end_tick,direction,rate`.
+// Blank lines and lines starting with '#' are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct DemandProfile {
+    pub entries: Vec<DemandEntry>,
+}
+
+impl DemandProfile {
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = parse_entry(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+            entries.push(entry);
+        }
+        Ok(Self { entries })
+    }
+
+    // Combined arrival rate for an approach at the given tick
+    pub fn rate_at(&self, tick: u64, dir: Direction) -> f64 {
+        self.entries
+            .iter()
+            .filter(|e| e.dir == dir && (e.start_tick..e.end_tick).contains(&tick))
+            .map(|e| e.rate)
+            .sum()
+    }
+}
+
+fn parse_entry(line: &str) -> Result<DemandEntry, String> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [start, end, dir, rate] = fields[..] else {
+        return Err(format!("expected 4 fields, found {}", fields.len()));
+    };
+    let start_tick = start.parse::<u64>().map_err(|e| format!("start tick '{}': {}", start, e))?;
+    let end_tick = end.parse::<u64>().map_err(|e| format!("end tick '{}': {}", end, e))?;
+    let rate = rate.parse::<f64>().map_err(|e| format!("rate '{}': {}", rate, e))?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("rate {} must be between 0 and 1 vehicles per tick", rate));
+    }
+    Ok(DemandEntry {
+        start_tick,
+        end_tick,
+        dir: parse_direction(dir)?,
+        rate,
+    })
+}
+
+pub(crate) fn parse_direction(s: &str) -> Result<Direction, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "n" | "north" => Ok(Direction::North),
+        "s" | "south" => Ok(Direction::South),
+        "e" | "east" => Ok(Direction::East),
+        "w" | "west" => Ok(Direction::West),
+        _ => Err(format!("unknown direction '{}'", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn turn_mix_follows_each_approachs_weights() {
+        let mut distribution = SpawnDistribution::uniform();
+        distribution.set_turn_weights(Direction::North, TurnWeights { left: 1, right: 0, straight: 9 });
+        distribution.set_turn_weights(Direction::East, TurnWeights { left: 1, right: 1, straight: 2 });
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut straight_share = |dir| {
+            let straights = (0..4000).filter(|_| distribution.sample_turn(dir, &mut rng) == Turn::Straight).count();
+            straights as f32 / 4000.0
+        };
+        let (north, east) = (straight_share(Direction::North), straight_share(Direction::East));
+        assert!((north - 0.9).abs() < 0.03, "north {}", north);
+        assert!((east - 0.5).abs() < 0.03, "east {}", east);
+    }
+}
@@ -0,0 +1,10 @@
+use crate::Direction;
+
+// Notable things that happened during the most recent World::update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimEvent {
+    ExcessiveDwell { id: u32 }, // Vehicle has been inside the box longer than max_dwell_ticks
+    Stalled { id: u32 }, // Vehicle made no progress for stall_ticks without waiting at a red light
+    Departed { id: u32, exit: Direction }, // Vehicle left the screen through the given edge
+    AllRedEntered { vehicles: Vec<u32> }, // Controller switched to AllRed; ids of the vehicles occupying the box
+}
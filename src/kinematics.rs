@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use crate::TICK_DURATION;
+
+// Motion helpers shared by the braking and signal logic.
+// Units: speeds in px/tick, decelerations in px/tick², distances in px, times in ticks.
+
+// Distance covered while braking from speed to a standstill at a constant deceleration
+pub const fn stopping_distance(speed: f32, decel: f32) -> f32 {
+    if speed <= 0.0 {
+        return 0.0;
+    }
+    speed * speed / (2.0 * decel)
+}
+
+// Highest speed from which a vehicle can still stop within distance; the inverse of stopping_distance
+pub fn braking_speed(distance: f32, decel: f32) -> f32 {
+    (2.0 * decel * distance.max(0.0)).sqrt()
+}
+
+// True when a vehicle can neither stop before the line nor reach it before the light turns red
+pub fn in_dilemma_zone(speed: f32, decel: f32, distance: f32, ticks_to_red: f32) -> bool {
+    if speed <= 0.0 {
+        return false;
+    }
+    let can_stop = stopping_distance(speed, decel) <= distance;
+    let can_clear = distance / speed <= ticks_to_red;
+    !can_stop && !can_clear
+}
+
+// Maps real-world units onto simulation units
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicalScale {
+    pub px_per_meter: f32,
+    pub ticks_per_second: f32,
+}
+
+impl PhysicalScale {
+    // A VEHICLE_SIZE square is roughly a 5 m car, and one tick lasts TICK_DURATION
+    pub fn standard() -> Self {
+        Self {
+            px_per_meter: 4.0,
+            ticks_per_second: 1.0 / TICK_DURATION.as_secs_f32(),
+        }
+    }
+
+    pub fn kmh_to_px_per_tick(&self, kmh: f32) -> f32 {
+        kmh / 3.6 * self.px_per_meter / self.ticks_per_second
+    }
+
+    // Logical controller time covering `seconds` of real time
+    pub fn seconds_to_duration(&self, seconds: f32) -> Duration {
+        TICK_DURATION.mul_f32(seconds * self.ticks_per_second)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dilemma_zone_only_when_neither_stopping_nor_clearing_works() {
+        // Needs 50 px to stop from 5 px/tick at 0.25 px/tick²
+        assert!(!in_dilemma_zone(5.0, 0.25, 100.0, 2.0), "far enough back to stop");
+        assert!(!in_dilemma_zone(5.0, 0.25, 20.0, 10.0), "close enough to clear");
+        assert!(in_dilemma_zone(5.0, 0.25, 30.0, 2.0));
+        assert!(!in_dilemma_zone(0.0, 0.25, 30.0, 0.0), "already standing");
+    }
+
+    #[test]
+    fn stopping_distance_known_values() {
+        assert_eq!(stopping_distance(5.0, 1.0), 12.5);
+        assert_eq!(stopping_distance(5.0, 0.25), 50.0);
+        assert_eq!(stopping_distance(0.0, 1.0), 0.0);
+        assert_eq!(stopping_distance(-1.0, 1.0), 0.0);
+        assert_eq!(braking_speed(stopping_distance(4.0, 0.5), 0.5), 4.0);
+    }
+
+    #[test]
+    fn fifty_kmh_converts_to_px_per_tick() {
+        // 50 km/h is 13.9 m/s, so 55.6 px/s spread over 60 ticks
+        let scale = PhysicalScale { px_per_meter: 4.0, ticks_per_second: 60.0 };
+        assert!((scale.kmh_to_px_per_tick(50.0) - 0.9259).abs() < 1e-4);
+        assert_eq!(scale.kmh_to_px_per_tick(0.0), 0.0);
+
+        // A 30 s cycle is 1800 ticks of TICK_DURATION
+        let cycle = scale.seconds_to_duration(30.0);
+        assert!(cycle.abs_diff(TICK_DURATION * 1800) < Duration::from_millis(1), "{:?}", cycle);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::Direction;
+use crate::config::Config;
+
+// Screen placement of overlay elements, kept free of SDL so it can be checked headless
+
+pub const QUEUE_BADGE_SIZE: u32 = 20;
+
+// Top-left corner of the queue-length badge for an approach, beside its traffic light at the stop line
+pub fn queue_badge_position(config: &Config, dir: Direction) -> Option<(i32, i32)> {
+    let (x, y) = signal_position(config, dir)?;
+    let offset = QUEUE_BADGE_SIZE as i32 + 5;
+    match dir {
+        Direction::North => Some((x, y - offset)),
+        Direction::South => Some((x, y + offset)),
+        Direction::East => Some((x + offset, y)),
+        _ => Some((x - offset, y)),
+    }
+}
+
+pub const SIGNAL_SIZE: u32 = 20;
+
+// Top-left corner of the traffic light square for an approach, beside its stop line on the curb side
+pub fn signal_position(config: &Config, dir: Direction) -> Option<(i32, i32)> {
+    let (left, top) = (config.intersection_x_start() as i32, config.intersection_y_start() as i32);
+    let (right, bottom) = (config.intersection_x_end() as i32, config.intersection_y_end() as i32);
+    let before = light_before_lane(config, dir);
+    match dir {
+        Direction::North => Some((beside_lane(config, config.southbound_lane_x(), before), top - 25)),
+        Direction::South => Some((beside_lane(config, config.northbound_lane_x(), before), bottom + 5)),
+        Direction::East => Some((right + 5, beside_lane(config, config.westbound_lane_y(), before))),
+        Direction::West => Some((left - 25, beside_lane(config, config.eastbound_lane_y(), before))),
+        Direction::AllRed => None,
+    }
+}
+
+// True when the curb of dir, and so its light, is on the low-coordinate side of its lane
+fn light_before_lane(config: &Config, dir: Direction) -> bool {
+    matches!(dir, Direction::North | Direction::East) != config.drive_on_left
+}
+
+// Start of a SIGNAL_SIZE span just outside the half of the road centred on `lane`
+fn beside_lane(config: &Config, lane: i32, before: bool) -> i32 {
+    let half_lane_span = config.road_width as i32 / 4;
+    if before { lane - half_lane_span - 5 - SIGNAL_SIZE as i32 } else { lane + half_lane_span + 5 }
+}
+
+// Top-left corner of the countdown text for an approach, on the side of its light away from
+// the queue badge
+pub fn countdown_position(config: &Config, dir: Direction) -> Option<(i32, i32)> {
+    let (x, y) = signal_position(config, dir)?;
+    let size = SIGNAL_SIZE as i32;
+    let before = light_before_lane(config, dir);
+    match dir {
+        Direction::North | Direction::South if before => Some((x - 40, y + 2)),
+        Direction::North | Direction::South => Some((x + size + 5, y + 2)),
+        _ if before => Some((x, y - size)),
+        _ => Some((x, y + size + 2)),
+    }
+}
+
+const LANE_ARROW_LENGTH: i32 = 30;
+const LANE_ARROW_BARB: i32 = 8;
+const LANE_ARROW_SETBACK: i32 = 40; // Distance from the stop line to the arrow tip
+
+// Pavement arrow in the outer (through) lane of dir, pointing in the direction of travel.
+// Returns [tail, tip, left barb, right barb]; the arrow is drawn as tail-tip plus tip-barb lines.
+pub fn lane_arrow(config: &Config, dir: Direction) -> Option<[(i32, i32); 4]> {
+    let (left, top) = (config.intersection_x_start() as i32, config.intersection_y_start() as i32);
+    let (right, bottom) = (config.intersection_x_end() as i32, config.intersection_y_end() as i32);
+    let shift = config.turn_lane_shift() * config.traffic_side();
+    let (tip, step) = match dir {
+        Direction::North => ((config.southbound_lane_x() - shift, top - LANE_ARROW_SETBACK), (0, 1)),
+        Direction::South => ((config.northbound_lane_x() + shift, bottom + LANE_ARROW_SETBACK), (0, -1)),
+        Direction::East => ((right + LANE_ARROW_SETBACK, config.westbound_lane_y() - shift), (-1, 0)),
+        Direction::West => ((left - LANE_ARROW_SETBACK, config.eastbound_lane_y() + shift), (1, 0)),
+        Direction::AllRed => return None,
+    };
+    let tail = (tip.0 - step.0 * LANE_ARROW_LENGTH, tip.1 - step.1 * LANE_ARROW_LENGTH);
+    let base = (tip.0 - step.0 * LANE_ARROW_BARB, tip.1 - step.1 * LANE_ARROW_BARB);
+    // Perpendicular to the travel direction
+    let side = (-step.1 * LANE_ARROW_BARB, step.0 * LANE_ARROW_BARB);
+    Some([
+        tail,
+        tip,
+        (base.0 - side.0, base.1 - side.1),
+        (base.0 + side.0, base.1 + side.1),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_badges_sit_beside_each_light() {
+        let config = Config::default();
+        let expected = [
+            (Direction::North, (325, 200)),
+            (Direction::South, (455, 380)),
+            (Direction::East, (480, 225)),
+            (Direction::West, (300, 355)),
+        ];
+        for (dir, position) in expected {
+            assert_eq!(queue_badge_position(&config, dir), Some(position), "{:?}", dir);
+        }
+        assert_eq!(queue_badge_position(&config, Direction::AllRed), None);
+    }
+
+    #[test]
+    fn lane_arrows_point_along_travel_towards_the_box() {
+        let config = Config::default();
+        let travel = [
+            (Direction::North, (0, 1)),
+            (Direction::South, (0, -1)),
+            (Direction::East, (-1, 0)),
+            (Direction::West, (1, 0)),
+        ];
+        for (dir, (dx, dy)) in travel {
+            let [tail, tip, left, right] = lane_arrow(&config, dir).unwrap();
+            assert_eq!((tip.0 - tail.0, tip.1 - tail.1), (dx * LANE_ARROW_LENGTH, dy * LANE_ARROW_LENGTH), "{:?}", dir);
+            // Barbs sit symmetrically either side of the shaft, LANE_ARROW_BARB back from the tip
+            let base = (tip.0 - dx * LANE_ARROW_BARB, tip.1 - dy * LANE_ARROW_BARB);
+            assert_eq!((left.0 + right.0, left.1 + right.1), (2 * base.0, 2 * base.1), "{:?}", dir);
+            assert_ne!(left, right);
+            // The tip stays short of the stop line, LANE_ARROW_SETBACK before the box
+            let box_edge = match dir {
+                Direction::North => config.intersection_y_start() as i32,
+                Direction::South => config.intersection_y_end() as i32,
+                Direction::East => config.intersection_x_end() as i32,
+                _ => config.intersection_x_start() as i32,
+            };
+            let along = if dx == 0 { tip.1 } else { tip.0 };
+            assert_eq!(along + (dx + dy) * LANE_ARROW_SETBACK, box_edge, "{:?}", dir);
+        }
+        assert_eq!(lane_arrow(&config, Direction::AllRed), None);
+    }
+
+    #[test]
+    fn lights_stay_beside_the_road_at_any_width() {
+        for road_width in [100, 160] {
+            let config = Config::default().with_road_width(road_width);
+            for dir in Direction::APPROACHES {
+                let (x, y) = signal_position(&config, dir).unwrap();
+                let (start, road_start) = match dir {
+                    Direction::North | Direction::South => (x, config.road_x() as i32),
+                    _ => (y, config.road_y() as i32),
+                };
+                let road_end = road_start + road_width as i32;
+                let end = start + SIGNAL_SIZE as i32;
+                let gap = if end <= road_start { road_start - end } else { start - road_end };
+                assert!((0..=5).contains(&gap), "{:?} light {} px from a {} px road", dir, gap, road_width);
+            }
+        }
+    }
+
+    #[test]
+    fn keep_left_moves_lights_to_the_other_curb() {
+        let right = Config::default();
+        let left = Config::default().with_drive_on_left(true);
+        for dir in [Direction::North, Direction::South] {
+            let (x_right, y_right) = signal_position(&right, dir).unwrap();
+            let (x_left, y_left) = signal_position(&left, dir).unwrap();
+            assert_eq!(x_left, right.window_width as i32 - x_right - SIGNAL_SIZE as i32, "{:?}", dir);
+            assert_eq!(y_left, y_right, "{:?}", dir);
+        }
+    }
+}
@@ -1,10 +1,38 @@
+pub mod ascii;
+pub mod camera;
+pub mod capture;
+pub mod config;
+pub mod controls;
+pub mod demand;
+pub mod events;
+pub mod kinematics;
+pub mod layout;
+pub mod metrics;
+pub mod network;
+pub mod pedestrian;
+pub mod perf;
+pub mod scenario;
+pub mod spatial;
 pub mod traffic_light;
 pub mod vehicle;
 
-use rand::Rng;
-use traffic_light::TrafficLightController;
-use vehicle::{Vehicle, generate_path};
+pub use config::Config;
+use demand::{DemandProfile, SpawnDistribution};
+use events::SimEvent;
+use kinematics::PhysicalScale;
+use metrics::{Metrics, NearMiss, Stats};
+use pedestrian::Pedestrian;
+use perf::PerfStats;
+use scenario::{Scenario, ScenarioVehicle};
+use spatial::SpatialGrid;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use traffic_light::{SignalInputs, TrafficLightController};
+use std::time::{Duration, Instant};
+pub use vehicle::{Vehicle, VehicleKind, generate_path};
+use vehicle::{rects_overlap, segment_heading};
 
+// Default geometry, used by Config::default(); a World reads its own through World::config
 pub const WINDOW_WIDTH: u32 = 800;
 pub const WINDOW_HEIGHT: u32 = 600;
 pub const ROAD_WIDTH: u32 = 100;
@@ -21,12 +49,34 @@ pub const NORTHBOUND_LANE_X: i32 = (ROAD_X + ROAD_WIDTH / 2 + ROAD_X + ROAD_WIDT
 pub const SOUTHBOUND_LANE_X: i32 = (ROAD_X + ROAD_WIDTH / 2 + ROAD_X) as i32 / 2;
 pub const EASTBOUND_LANE_Y: i32 = (ROAD_Y + ROAD_WIDTH / 2 + ROAD_Y + ROAD_WIDTH) as i32 / 2;
 pub const WESTBOUND_LANE_Y: i32 = (ROAD_Y + ROAD_WIDTH / 2 + ROAD_Y) as i32 / 2;
+// Each approach is split into a through/right lane on the outside and a dedicated left-turn lane
+// next to the center line (mirrored when driving on the left, see Config::drive_on_left), this far either side of the *_LANE_X / *_LANE_Y line
+pub const TURN_LANE_SHIFT: i32 = ROAD_WIDTH as i32 / 8;
 
 pub const VEHICLE_SIZE: u32 = 20;
 pub const VEHICLE_SAFETY_GAP: u32 = 10;
 
+pub const TICK_DURATION: Duration = Duration::from_millis(16); // One simulation step at ~60 updates per second
+pub const MAX_SPEED: f32 = 5.0; // Cruising speed in pixels per tick
+pub const MAX_VEHICLES: usize = 200; // Default cap on vehicles in the world, spawns beyond it are refused
+const FOLLOW_SAMPLE_SPACING: f32 = 5.0; // Route distance between footprints checked for a vehicle in front
+pub const NEAR_MISS_DISTANCE: u32 = VEHICLE_SAFETY_GAP; // Default near-miss distance: closer than followers keep
+pub const LEFT_TURN_GAP: i32 = 150; // Oncoming distance a turn across traffic needs clear on a shared green
+pub const ACCELERATION: f32 = 0.25; // Speed gained per tick when pulling away
+pub const DECELERATION: f32 = 0.25; // Speed lost per tick while braking for a light
+pub const MIN_APPROACH_SPEED: f32 = 1.0; // Crawl speed used to roll up to the stop line
+pub const REACTION_DISTANCE: i32 = kinematics::stopping_distance(MAX_SPEED, DECELERATION) as i32; // Distance from the stop line at which vehicles start braking
+pub const EXTENSION_DISTANCE: i32 = 60; // Approaching vehicles this close to the line can extend their green
+pub const MAX_DWELL_TICKS: u32 = 180; // No vehicle should need longer than this to cross the box
+pub const AVOID_GREEN_WEIGHT: u32 = 3; // Relative spawn weight of a red approach against the green one
+pub const REAL_SPEED_KMH: f32 = 50.0; // Urban speed limit used by the physical scale
+pub const REAL_CYCLE_SECONDS: f32 = 30.0; // Full N-S-E-W cycle length used by the physical scale
+pub const RIGHT_ON_RED_STOP_TICKS: u32 = 60; // Full stop a turn on red makes at the line before it may go
+pub const STALL_TICKS: u32 = 600; // Ticks without progress, outside a red light, before a vehicle counts as stalled
+
 // Directions of approach to the intersection
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     North,
     South,
@@ -35,73 +85,424 @@ pub enum Direction {
     AllRed,
 }
 
+impl Direction {
+    // The four real approaches in signal cycle order
+    pub const APPROACHES: [Direction; 4] = [Direction::North, Direction::South, Direction::East, Direction::West];
+
+    // Position in APPROACHES, None for AllRed
+    pub fn approach_index(self) -> Option<usize> {
+        Self::APPROACHES.iter().position(|&d| d == self)
+    }
+
+    // Edge a vehicle approaching from self leaves through after taking turn
+    pub fn exit(self, turn: Turn) -> Direction {
+        match (self, turn) {
+            (dir, Turn::Straight) => dir.opposite(),
+            (Direction::North, Turn::Left) | (Direction::South, Turn::Right) => Direction::East,
+            (Direction::North, Turn::Right) | (Direction::South, Turn::Left) => Direction::West,
+            (Direction::East, Turn::Left) | (Direction::West, Turn::Right) => Direction::South,
+            (Direction::East, Turn::Right) | (Direction::West, Turn::Left) => Direction::North,
+            (Direction::AllRed, _) => Direction::AllRed,
+        }
+    }
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+            Direction::AllRed => Direction::AllRed,
+        }
+    }
+}
+
+// What happens to a vehicle that drives off the screen
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoundaryMode {
+    Despawn, // Removed, after fading out if fade_ticks is set
+    Wrap,    // Re-enters from the opposite edge as a new arrival, keeping the vehicle count constant for load tests
+}
+
+// How vehicle positions advance each tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PositionMode {
+    Integer, // Whole-pixel, axis-by-axis stepping; a speed below 1 px/tick truncates to no movement
+    Float,   // Sub-pixel positions moving straight towards the waypoint
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Turn {
     Left,
     Right,
     Straight,
 }
 
+// The parts of an active vehicle others react to, copied once per tick instead of cloning its path
+#[derive(Debug, Clone, Copy)]
+struct VehicleSnapshot {
+    id: u32,
+    dir: Direction,
+    turn: Turn,
+    x: i32,
+    y: i32,
+    envelope: (i32, i32, u32, u32),
+    path_index: usize,
+    in_intersection: bool,
+    distance_to_stop_line: Option<i32>,
+}
+
+impl VehicleSnapshot {
+    fn of(v: &Vehicle, config: &Config) -> Self {
+        Self {
+            id: v.id,
+            dir: v.dir,
+            turn: v.turn,
+            x: v.x,
+            y: v.y,
+            envelope: v.envelope(),
+            path_index: v.path_index,
+            in_intersection: v.in_intersection(config),
+            distance_to_stop_line: v.distance_to_stop_line(),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct World {
+    config: Config, // Fixed for the life of the world, as vehicle paths are generated from it
     pub vehicles: Vec<Vehicle>,
+    pub pedestrians: Vec<Pedestrian>, // Waiting at a curb or on a crosswalk
     pub controller: TrafficLightController,
+    pub cruise_speed: f32, // max_speed in px/tick given to new vehicles unless the spawn overrides it
+    pub max_vehicles: usize, // Spawns are refused while this many vehicles are on the map
+    pub follow_distance: u32, // Bumper-to-bumper gap a vehicle keeps to the one ahead in its direction of travel
+    pub headway_ticks: f32, // Extra following gap per px/tick of speed; 0 keeps the fixed follow_distance
+    pub near_miss_distance: u32, // Route distance between two moving vehicles below which they count as a near miss
+    pub reaction_distance: i32,
+    pub extension_distance: i32,
+    pub max_dwell_ticks: u32,
+    pub stall_ticks: u32,
+    pub fade_ticks: u32, // Ticks a passed vehicle stays visible while fading out, 0 disables fading
+    pub position_mode: PositionMode,
+    pub boundary_mode: BoundaryMode,
+    pub avoid_green: bool, // Bias random spawns towards approaches that are currently red
+    pub right_on_red: bool, // Curb turns (right, or left when driving on the left) may go on red after a full stop
+    pub spawn_distribution: SpawnDistribution,
     next_id: u32,
+    next_pedestrian_id: u32,
+    ticks: u64,
+    metrics: Metrics,
+    seed: u64,
+    #[cfg_attr(feature = "serde", serde(skip, default = "unseeded_rng"))]
+    rng: StdRng, // Not serialized; from_json reseeds it from seed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    perf: Option<PerfStats>,
+    physical_scale: Option<PhysicalScale>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    events: Vec<SimEvent>,
+    all_red_trigger: Vec<u32>,
+    collisions: u32,
+    overlapping: Vec<(u32, u32)>, // Pairs overlapping after the last tick, so each contact counts once
+    near_misses: u32,
+    near_miss_log: Vec<NearMiss>,
+    close_pairs: Vec<(u32, u32)>, // Pairs within near_miss_distance after the last tick, so each encounter counts once
 }
 
 impl World {
+    // Seeds from entropy; the chosen seed is still recorded so the run can be replayed
     pub fn new() -> Self {
+        Self::with_seed(rand::thread_rng().r#gen())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_seed_and_config(seed, Config::default())
+    }
+
+    // Window size and road geometry other than the defaults; config should pass Config::validate
+    pub fn with_config(config: Config) -> Self {
+        Self::with_seed_and_config(rand::thread_rng().r#gen(), config)
+    }
+
+    pub fn with_seed_and_config(seed: u64, config: Config) -> Self {
+        debug_assert!(config.validate().is_ok(), "invalid config {:?}", config);
         Self {
+            config,
             vehicles: Vec::new(),
+            pedestrians: Vec::new(),
             controller: TrafficLightController::new(),
+            cruise_speed: MAX_SPEED,
+            max_vehicles: MAX_VEHICLES,
+            follow_distance: VEHICLE_SAFETY_GAP,
+            headway_ticks: 0.0,
+            near_miss_distance: NEAR_MISS_DISTANCE,
+            reaction_distance: REACTION_DISTANCE,
+            extension_distance: EXTENSION_DISTANCE,
+            max_dwell_ticks: MAX_DWELL_TICKS,
+            stall_ticks: STALL_TICKS,
+            fade_ticks: 0,
+            position_mode: PositionMode::Integer,
+            boundary_mode: BoundaryMode::Despawn,
+            avoid_green: false,
+            right_on_red: false,
+            spawn_distribution: SpawnDistribution::uniform(),
             next_id: 0,
+            next_pedestrian_id: 0,
+            ticks: 0,
+            metrics: Metrics::new(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            perf: None,
+            physical_scale: None,
+            events: Vec::new(),
+            all_red_trigger: Vec::new(),
+            collisions: 0,
+            overlapping: Vec::new(),
+            near_misses: 0,
+            near_miss_log: Vec::new(),
+            close_pairs: Vec::new(),
         }
     }
 
-    fn count_waiting_vehicles(&self) -> u32 {
-        let mut waiting_vehicles = 0;
-        for v in &self.vehicles {
-            if v.dir == self.controller.current {
-                if v.path_index == 1 {
-                    waiting_vehicles += 1;
-                }
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    // Events emitted during the most recent update
+    pub fn events(&self) -> &[SimEvent] {
+        &self.events
+    }
+
+    // Number of updates run so far
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    // Vehicles that made it through the intersection and left the screen
+    pub fn passed_count(&self) -> u32 {
+        self.metrics.vehicles_passed
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    // Summary of the run so far
+    pub fn stats(&self) -> Stats {
+        let minutes = self.ticks as f32 * TICK_DURATION.as_secs_f32() / 60.0;
+        Stats {
+            ticks: self.ticks,
+            vehicles_passed: self.metrics.vehicles_passed,
+            throughput_per_minute: if minutes > 0.0 { self.metrics.vehicles_passed as f32 / minutes } else { 0.0 },
+            avg_wait_ticks: self.metrics.avg_wait_ticks(),
+            avg_crossing_ticks: self.metrics.avg_crossing_ticks(),
+            avg_stops: self.metrics.avg_stops(),
+            collisions: self.collisions,
+            near_misses: self.near_misses,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Timing is only measured while enabled, so a disabled world pays nothing for it
+    pub fn set_perf_stats_enabled(&mut self, enabled: bool) {
+        self.perf = if enabled { Some(PerfStats::new()) } else { None };
+    }
+
+    pub fn perf_stats(&self) -> Option<&PerfStats> {
+        self.perf.as_ref()
+    }
+
+    // Rolling average duration of update in microseconds, 0 when timing is disabled
+    pub fn last_update_micros(&self) -> f64 {
+        self.perf.as_ref().map_or(0.0, |p| p.average_micros())
+    }
+
+    // Switches speeds and light timings to real-world values (REAL_SPEED_KMH and a
+    // REAL_CYCLE_SECONDS cycle) under the given scale; None restores the default units.
+    // Real speeds are below a pixel per tick, so the scaled mode also moves in float positions.
+    pub fn set_physical_scale(&mut self, scale: Option<PhysicalScale>) {
+        let (speed, phase, mode) = match scale {
+            Some(scale) => (
+                scale.kmh_to_px_per_tick(REAL_SPEED_KMH),
+                scale.seconds_to_duration(REAL_CYCLE_SECONDS / Direction::APPROACHES.len() as f32),
+                PositionMode::Float,
+            ),
+            None => (MAX_SPEED, traffic_light::MAX_PHASE_DURATION, PositionMode::Integer),
+        };
+        self.cruise_speed = speed;
+        for v in &mut self.vehicles {
+            v.max_speed = v.kind.max_speed(speed);
+        }
+        self.reaction_distance = kinematics::stopping_distance(speed, DECELERATION) as i32;
+        self.position_mode = mode;
+        self.controller.set_max_phase_duration(phase);
+        self.physical_scale = scale;
+    }
+
+    pub fn physical_scale(&self) -> Option<PhysicalScale> {
+        self.physical_scale
+    }
+
+    // One-line description of the settings needed to reproduce this run
+    pub fn config_summary(&self) -> String {
+        format!(
+            "Seed: {} | Road: {}px{} | Phase: {}s | Strategy: {:?}{} | Adaptive timing: {} | Scramble: {}",
+            self.seed,
+            self.config.road_width,
+            if self.config.drive_on_left { " keep-left" } else { "" },
+            phase_summary(self.controller.phase_durations()),
+            self.controller.strategy(),
+            if self.controller.paired() { " (paired)" } else { "" },
+            if self.controller.adaptive_timing() { "on" } else { "off" },
+            match self.controller.scramble_every() {
+                0 => "off".to_string(),
+                n => format!("every {} cycles", n),
+            },
+        )
+    }
+
+    // Picks a spawn approach using the world's seeded RNG
+    // With avoid_green set, the currently green approach is AVOID_GREEN_WEIGHT times less
+    // likely than each red one, so queues build up where they will be visibly released.
+    pub fn random_direction(&mut self) -> Direction {
+        let weights = Direction::APPROACHES.map(|dir| {
+            if self.avoid_green && self.controller.serves(dir) { 1 } else { AVOID_GREEN_WEIGHT }
+        });
+        let mut pick = self.rng.gen_range(0..weights.iter().sum::<u32>());
+        for (dir, weight) in Direction::APPROACHES.into_iter().zip(weights) {
+            if pick < weight {
+                return dir;
+            }
+            pick -= weight;
+        }
+        Direction::West
+    }
+
+    // Vehicles sitting at each stop line, indexed like Direction::APPROACHES
+    fn count_waiting_vehicles(&self) -> [u32; 4] {
+        let mut waiting_vehicles = [0; 4];
+        for v in self.vehicles.iter().filter(|v| v.path_index == 1) {
+            if let Some(i) = v.dir.approach_index() {
+                waiting_vehicles[i] += 1;
             }
         }
         waiting_vehicles
     }
 
-    fn check_cars_in_intersection(&self) -> bool {
-        for v in &self.vehicles {
-            if v.x < INTERSECTION_X_END as i32
-                && v.x + VEHICLE_SIZE as i32 > INTERSECTION_X_START as i32
-                && v.y < INTERSECTION_Y_END as i32
-                && v.y + VEHICLE_SIZE as i32 > INTERSECTION_Y_START as i32
-            {
-                return true;
+    // Ids of the vehicles queued on an approach, first arrival first
+    pub fn queue_order(&self, dir: Direction) -> Vec<u32> {
+        let mut queued: Vec<&Vehicle> = self
+            .vehicles
+            .iter()
+            .filter(|v| v.dir == dir && v.path_index <= 1 && v.queued_at.is_some())
+            .collect();
+        queued.sort_by_key(|v| (v.queued_at, v.id));
+        queued.iter().map(|v| v.id).collect()
+    }
+
+    // Longest wait_ticks among the vehicles still approaching each stop line
+    fn longest_waits(&self) -> [u32; 4] {
+        let mut longest = [0; 4];
+        for v in self.vehicles.iter().filter(|v| v.path_index <= 1) {
+            if let Some(i) = v.dir.approach_index() {
+                longest[i] = longest[i].max(v.wait_ticks);
             }
         }
-        false
+        longest
+    }
+
+    // Vehicles of an approach standing still before the box, i.e. the visible queue
+    pub fn queue_length(&self, dir: Direction) -> u32 {
+        self.vehicles
+            .iter()
+            .filter(|v| v.dir == dir && v.path_index <= 1 && v.speed == 0.0)
+            .count() as u32
+    }
+
+    // Ids of the vehicles whose rectangle overlaps the intersection box, in spawn order
+    pub fn vehicles_in_intersection(&self) -> Vec<u32> {
+        self.vehicles.iter().filter(|v| v.in_intersection(&self.config)).map(|v| v.id).collect()
+    }
+
+    // Vehicles that were in the box when the current AllRed began; empty outside AllRed
+    pub fn all_red_trigger(&self) -> &[u32] {
+        &self.all_red_trigger
+    }
+
+    // Approaching vehicles on the green approach that can neither stop nor clear before it ends
+    pub fn vehicles_in_dilemma_zone(&self) -> Vec<u32> {
+        let ticks_to_red = self.controller.remaining_time().as_secs_f32() / TICK_DURATION.as_secs_f32();
+        self.vehicles
+            .iter()
+            .filter(|v| self.controller.serves(v.dir))
+            .filter(|v| {
+                v.distance_to_stop_line().is_some_and(|d| {
+                    kinematics::in_dilemma_zone(v.speed, DECELERATION, d as f32, ticks_to_red)
+                })
+            })
+            .map(|v| v.id)
+            .collect()
+    }
+
+    // A vehicle of the green approach within extension_distance of its stop line
+    fn vehicle_approaching_green(&self) -> bool {
+        self.vehicles.iter().any(|v| {
+            self.controller.serves(v.dir)
+                && v.distance_to_stop_line().is_some_and(|d| d <= self.extension_distance)
+        })
+    }
+
+    pub fn cars_in_intersection(&self) -> bool {
+        self.vehicles.iter().any(|v| v.in_intersection(&self.config))
+    }
+
+    // Recovery for jammed demos: drop only the vehicles inside the box and restart the
+    // signal from AllRed, leaving queued vehicles where they are
+    pub fn clear_intersection(&mut self) {
+        let config = self.config;
+        self.vehicles.retain(|v| !v.in_intersection(&config));
+        self.controller.force_all_red();
+    }
+
+    // Empties the map of vehicles but leaves the signal, pedestrians and counters alone, to set up a
+    // scenario mid-cycle. Ids keep counting up, so stats and events keyed by id never see one reused.
+    pub fn clear_vehicles(&mut self) {
+        self.vehicles.clear();
+        self.all_red_trigger.clear();
+        self.overlapping.clear();
+        self.close_pairs.clear();
     }
 
     fn check_vehicles_on_stop_line(&self) -> bool {
+        let (x_start, x_end) = (self.config.intersection_x_start() as i32, self.config.intersection_x_end() as i32);
+        let (y_start, y_end) = (self.config.intersection_y_start() as i32, self.config.intersection_y_end() as i32);
         for v in &self.vehicles {
+            let (x, y, width, height) = v.envelope();
+            let (width, height) = (width as i32, height as i32);
             match v.dir {
                 Direction::North => { // Southbound lane, approaching from North
-                    if v.y <= INTERSECTION_Y_START as i32 && v.y + VEHICLE_SIZE as i32 > INTERSECTION_Y_START as i32 {
+                    if y <= y_start && y + height > y_start {
                         return true;
                     }
                 },
                 Direction::South => { // Northbound lane, approaching from South
-                    if v.y + VEHICLE_SIZE as i32 >= INTERSECTION_Y_END as i32 && v.y < INTERSECTION_Y_END as i32 {
+                    if y + height >= y_end && y < y_end {
                         return true;
                     }
                 },
                 Direction::East => { // Westbound lane, approaching from East
-                    if v.x <= INTERSECTION_X_START as i32 && v.x + VEHICLE_SIZE as i32 > INTERSECTION_X_START as i32 {
+                    if x <= x_start && x + width > x_start {
                         return true;
                     }
                 },
                 Direction::West => { // Eastbound lane, approaching from West
-                    if v.x + VEHICLE_SIZE as i32 >= INTERSECTION_X_END as i32 && v.x < INTERSECTION_X_END as i32 {
+                    if x + width >= x_end && x < x_end {
                         return true;
                     }
                 },
@@ -111,17 +512,79 @@ impl World {
         false
     }
 
+    // Free road past the box on each exit arm, indexed like Direction::APPROACHES by the edge the
+    // arm leads to: the distance from the box to the nearest vehicle stopped out on the arm, less
+    // the length and follow distance of every vehicle already committed to queue before it.
+    // i32::MAX when nothing is stopped there.
+    fn exit_room(&self) -> [i32; 4] {
+        let (x_start, x_end) = (self.config.intersection_x_start() as i32, self.config.intersection_x_end() as i32);
+        let (y_start, y_end) = (self.config.intersection_y_start() as i32, self.config.intersection_y_end() as i32);
+        // Distance past the box of every vehicle in it or beyond it, negative while still inside
+        let bound: Vec<(usize, i32, &Vehicle)> = self
+            .vehicles
+            .iter()
+            .filter(|v| !v.passed && (v.path_index >= 2 || v.in_intersection(&self.config)))
+            .filter_map(|v| {
+                let exit = v.dir.exit(v.turn);
+                let (x, y, width, height) = v.envelope();
+                let distance = match exit {
+                    Direction::North => y_start - (y + height as i32),
+                    Direction::South => y - y_end,
+                    Direction::East => x - x_end,
+                    _ => x_start - (x + width as i32),
+                };
+                Some((exit.approach_index()?, distance, v))
+            })
+            .collect();
+        let mut room = [i32::MAX; 4];
+        for &(i, distance, v) in &bound {
+            if v.speed == 0.0 && distance >= 0 {
+                room[i] = room[i].min(distance);
+            }
+        }
+        for (i, free) in room.iter_mut().enumerate() {
+            if *free == i32::MAX {
+                continue;
+            }
+            let jam = *free;
+            for &(_, _, v) in bound.iter().filter(|&&(j, distance, _)| j == i && distance < jam) {
+                *free -= (v.kind.length() + self.follow_distance) as i32;
+            }
+        }
+        room
+    }
+
     fn update_vehicle_positions(&mut self) {
-        let vehicles_clone = self.vehicles.clone();
+        // Positions at the start of the tick, so every vehicle reacts to the same picture
+        let snapshot: Vec<VehicleSnapshot> =
+            self.vehicles.iter().filter(|v| !v.passed).map(|v| VehicleSnapshot::of(v, &self.config)).collect();
+        let mut grid = SpatialGrid::new(VEHICLE_SIZE as i32);
+        for (i, other) in snapshot.iter().enumerate() {
+            grid.insert(i, other.x, other.y);
+        }
+        // Grid positions are square corners; a longer envelope reaches this far back from its square
+        let longest = snapshot.iter().map(|other| other.envelope.2.max(other.envelope.3)).max().unwrap_or(VEHICLE_SIZE);
+        let reaction_distance = self.reaction_distance;
+        let headway_ticks = self.headway_ticks;
+        let follow_distance = self.follow_distance as f32;
+        let stall_ticks = self.stall_ticks;
+        let position_mode = self.position_mode;
+        let ticks = self.ticks;
+        let config = self.config;
+        let crossing_turn = config.crossing_turn();
+        let right_on_red = self.right_on_red && !self.controller.is_scramble();
+        // A flashing red releases one vehicle at a time, so there is no shared green to yield on
+        let paired = self.controller.paired() && !self.controller.is_flashing();
+        let exit_room = self.exit_room();
         for v in &mut self.vehicles {
+            if v.passed {
+                continue;
+            }
+            let progress_before = (v.x, v.y, v.path_index);
 
-            let green_dir = self.controller.current;
-            let is_green = v.dir == green_dir;
+            let is_green = self.controller.is_green(v.dir);
 
-            let in_intersection = v.x < INTERSECTION_X_END as i32
-                && v.x + VEHICLE_SIZE as i32 > INTERSECTION_X_START as i32
-                && v.y < INTERSECTION_Y_END as i32
-                && v.y + VEHICLE_SIZE as i32 > INTERSECTION_Y_START as i32;
+            let in_intersection = v.in_intersection(&config);
 
             let at_intersection_border = v.path_index == 1 && !in_intersection;
             let mut stop_for_light = false;
@@ -129,133 +592,1177 @@ impl World {
                 stop_for_light = true;
             }
 
+            // A left turn (right when driving on the left) on a shared green crosses the oncoming
+            // lane, so it waits for a gap of LEFT_TURN_GAP in the opposing approach. Of two opposing
+            // crossing turns waiting at their lines the older one goes first, and the inner-lane queue
+            // behind the younger one cannot move, though the opposing through lane still can.
+            if at_intersection_border && is_green && paired && v.turn == crossing_turn {
+                let opposing: Vec<&VehicleSnapshot> =
+                    snapshot.iter().filter(|other| other.dir == v.dir.opposite()).collect();
+                let opposing_yields = opposing.iter().any(|other| {
+                    other.path_index == 1 && !other.in_intersection && other.turn == crossing_turn && other.id > v.id
+                });
+                stop_for_light = opposing.iter().any(|other| {
+                    other.in_intersection
+                        || (!(opposing_yields && other.turn == crossing_turn) && other.distance_to_stop_line.is_some_and(|d| d <= LEFT_TURN_GAP))
+                        || (other.path_index == 1 && (other.turn != crossing_turn || other.id < v.id))
+                });
+            }
+
+            // Oncoming traffic lets a crossing turn already in the box finish crossing its lanes
+            if at_intersection_border && is_green && paired && !stop_for_light {
+                stop_for_light = snapshot
+                    .iter()
+                    .any(|other| other.dir == v.dir.opposite() && other.turn == crossing_turn && other.in_intersection);
+            }
+
+            // Turn on red: a curb turn that has stood at its line for RIGHT_ON_RED_STOP_TICKS goes
+            // once nothing bound for the same exit is in the box or closing on it with a green
+            if stop_for_light && right_on_red && v.turn == config.curb_turn() && v.line_stop_ticks >= RIGHT_ON_RED_STOP_TICKS {
+                let exit = v.dir.exit(v.turn);
+                stop_for_light = snapshot.iter().any(|other| {
+                    other.id != v.id
+                        && other.dir.exit(other.turn) == exit
+                        && (other.in_intersection
+                            || (other.path_index <= 1
+                                && self.controller.serves(other.dir)
+                                && other.distance_to_stop_line.is_none_or(|d| d <= LEFT_TURN_GAP)))
+                });
+            }
+
             // If vehicle is already in the intersection, it should not stop for red light
             if in_intersection {
                 stop_for_light = false;
             }
 
+            // Don't block the box: a vehicle free to go still waits at its line until the exit arm
+            // it is heading for has room for its whole body past the box
+            let needed = (v.kind.length() as f32 + follow_distance) as i32;
+            let stop_for_exit = at_intersection_border
+                && !stop_for_light
+                && v.dir.exit(v.turn).approach_index().is_some_and(|i| exit_room[i] < needed);
+
+            // Checked on every segment, so vehicles keep their distance inside and past the box too
             let mut stop_for_collision = false;
-            if v.path_index < v.path.len() - 1 {
-                // Only check for collisions before and at the intersection - after that vehicles can move freely
-                let next_pos = v.path[v.path_index + 1];
-                let next_x = v.x + (next_pos.0 - v.x).signum() * 5;
-                let next_y = v.y + (next_pos.1 - v.y).signum() * 5;
-
-                for other in &vehicles_clone {
-                    if v.id == other.id {
-                        continue;
-                    }
+            let step = v.max_speed.max(MAX_SPEED).ceil();
+            // Time headway: the faster the vehicle, the more room it keeps to the one ahead
+            let reach = follow_distance + (v.speed * headway_ticks).round() + step;
+            // Footprints over the next `reach` pixels of the route, so the gap is measured along
+            // the direction of travel: through a turn, cars beside the arc are not in front
+            let samples = (reach / FOLLOW_SAMPLE_SPACING).ceil().max(1.0) as i32;
+            let probes: Vec<(i32, i32)> =
+                (1..=samples).map(|k| v.position_along_path(reach * k as f32 / samples as f32)).collect();
 
-                    // Bounding box collision detection with safety gap
-                    if next_x < other.x + (VEHICLE_SIZE + VEHICLE_SAFETY_GAP) as i32
-                        && next_x + (VEHICLE_SIZE + VEHICLE_SAFETY_GAP) as i32 > other.x
-                        && next_y < other.y + (VEHICLE_SIZE + VEHICLE_SAFETY_GAP) as i32
-                        && next_y + (VEHICLE_SIZE + VEHICLE_SAFETY_GAP) as i32 > other.y
-                    {
-                        stop_for_collision = true;
-                        break;
-                    }
+            for other in grid.nearby(v.x, v.y, reach.ceil() as i32 + longest as i32).map(|i| &snapshot[i]) {
+                if v.id == other.id {
+                    continue;
+                }
+                if probes.iter().any(|p| rects_overlap((p.0, p.1, VEHICLE_SIZE, VEHICLE_SIZE), other.envelope)) {
+                    stop_for_collision = true;
+                    break;
                 }
             }
 
-            let should_stop = stop_for_light || stop_for_collision;
+            let should_stop = stop_for_light || stop_for_exit || stop_for_collision;
 
-            if !should_stop {
-                if v.path_index < v.path.len() - 1 {
-                    let target = v.path[v.path_index + 1];
-                    let dx = target.0 - v.x;
-                    let dy = target.1 - v.y;
+            // Start braking once the stop line is within reaction distance and the light is not green
+            let braking_distance = v
+                .distance_to_stop_line()
+                .filter(|&d| !is_green && d <= reaction_distance);
 
-                    if dx.abs() < 5 && dy.abs() < 5 {
-                        v.path_index += 1;
-                    } else {
-                        let step = 5;
-                        if dx != 0 {
-                            v.x += dx.signum() * step;
-                        } else if dy != 0 {
-                            v.y += dy.signum() * step;
-                        }
-                    }
-                } 
+            if should_stop {
+                if v.speed > 0.0 {
+                    v.stop_count += 1;
+                }
+                v.speed = 0.0;
+                v.wait_ticks += 1;
+                if at_intersection_border {
+                    v.line_stop_ticks += 1;
+                }
+                if v.path_index <= 1 && v.queued_at.is_none() {
+                    v.queued_at = Some(ticks);
+                }
+            } else {
+                if let Some(distance) = braking_distance {
+                    // Follow the constant-deceleration profile that ends exactly at the stop line
+                    let limit = kinematics::braking_speed(distance as f32, DECELERATION);
+                    v.speed = v.speed.min(limit).max(MIN_APPROACH_SPEED.min(v.max_speed));
+                } else {
+                    v.speed = (v.speed + ACCELERATION).min(v.max_speed);
+                }
+
+                v.advance(position_mode);
+            }
+
+            // Watchdog: making no progress while not held by a red light or a full exit means the vehicle is stuck
+            let held_by_light = !is_green && v.path_index <= 1 && !in_intersection;
+            if (v.x, v.y, v.path_index) == progress_before && !held_by_light && !stop_for_exit {
+                v.stalled_ticks += 1;
+                if v.stalled_ticks == stall_ticks {
+                    self.events.push(SimEvent::Stalled { id: v.id });
+                }
+            } else {
+                v.stalled_ticks = 0;
+            }
+        }
+    }
+
+    // Pedestrians step off the curb only during a scramble once the box is empty of vehicles,
+    // and leave the world on reaching the far curb
+    fn update_pedestrians(&mut self) {
+        if self.controller.is_scramble() && !self.cars_in_intersection() {
+            for p in &mut self.pedestrians {
+                p.walking = true;
+            }
+        }
+        self.pedestrians.retain_mut(|p| !p.walking || !p.advance());
+    }
+
+    // Queues a pedestrian at a curb of the crosswalk across the arm of dir, alternating sides;
+    // they cross during the next pedestrian scramble
+    pub fn spawn_pedestrian(&mut self, dir: Direction) -> Option<u32> {
+        let id = self.next_pedestrian_id;
+        self.pedestrians.push(Pedestrian::new(&self.config, id, dir, id.is_multiple_of(2))?);
+        self.next_pedestrian_id += 1;
+        Some(id)
+    }
+
+    // True while any pedestrian is out on a crosswalk
+    pub fn pedestrians_crossing(&self) -> bool {
+        self.pedestrians.iter().any(|p| p.walking)
+    }
+
+    // Counts every pair of active vehicles whose squares start to overlap this tick
+    fn detect_collisions(&mut self) {
+        let active: Vec<&Vehicle> = self.vehicles.iter().filter(|v| !v.passed).collect();
+        // Computed once per vehicle; only pairs whose envelopes touch need their bodies compared
+        let envelopes: Vec<_> = active.iter().map(|v| v.envelope()).collect();
+        let mut overlapping = Vec::new();
+        for (i, v) in active.iter().enumerate() {
+            for (j, other) in active.iter().enumerate().skip(i + 1) {
+                if rects_overlap(envelopes[i], envelopes[j]) && v.overlaps(other) {
+                    overlapping.push((v.id.min(other.id), v.id.max(other.id)));
+                }
+            }
+        }
+        let new_contacts = overlapping.iter().filter(|pair| !self.overlapping.contains(pair)).count();
+        self.collisions += new_contacts as u32;
+        self.overlapping = overlapping;
+    }
+
+    // Vehicle pairs that have come into contact since the world was created
+    pub fn collisions(&self) -> u32 {
+        self.collisions
+    }
+
+    // Counts every pair of moving vehicles, not yet touching, where one is within near_miss_distance
+    // of driving into the other along its route. Measured along the route so that neighbours in
+    // parallel lanes, which pass only a few pixels apart, do not count.
+    fn detect_near_misses(&mut self) {
+        let moving: Vec<&Vehicle> = self.vehicles.iter().filter(|v| !v.passed && v.speed > 0.0).collect();
+        let distance = self.near_miss_distance as f32;
+        let longest = moving.iter().map(|v| v.kind.length()).max().unwrap_or(0).max(VEHICLE_SIZE);
+        let reach = (longest + self.near_miss_distance) as i32;
+        let mut close_pairs = Vec::new();
+        for (i, v) in moving.iter().enumerate() {
+            for other in &moving[i + 1..] {
+                if (v.x - other.x).abs() >= reach || (v.y - other.y).abs() >= reach {
+                    continue;
+                }
+                if v.overlaps(other) || !(v.closing_on(other, distance) || other.closing_on(v, distance)) {
+                    continue;
+                }
+                let (first, second) = if v.id < other.id { (v, other) } else { (other, v) };
+                let ids = (first.id, second.id);
+                if !self.close_pairs.contains(&ids) {
+                    self.near_misses += 1;
+                    self.near_miss_log.push(NearMiss {
+                        tick: self.ticks,
+                        ids,
+                        positions: ((first.x, first.y), (second.x, second.y)),
+                    });
+                }
+                close_pairs.push(ids);
+            }
+        }
+        self.close_pairs = close_pairs;
+    }
+
+    // Vehicle pairs that have come within near_miss_distance while both moving since the world was created
+    pub fn near_misses(&self) -> u32 {
+        self.near_misses
+    }
+
+    // Every near miss so far, oldest first
+    pub fn near_miss_log(&self) -> &[NearMiss] {
+        &self.near_miss_log
+    }
+
+    // Correctness tripwire: a vehicle stuck in the box points at a stall in the yield logic
+    fn track_dwell_times(&mut self) {
+        for v in &mut self.vehicles {
+            if !v.in_intersection(&self.config) {
+                v.ticks_in_box = 0;
+                continue;
+            }
+            v.ticks_in_box += 1;
+            if v.ticks_in_box == self.max_dwell_ticks + 1 {
+                self.events.push(SimEvent::ExcessiveDwell { id: v.id });
             }
         }
     }
 
+    // One tick of TICK_DURATION
     pub fn update(&mut self) {
-        if self.vehicles.is_empty() {
+        self.step(TICK_DURATION);
+    }
+
+    // One tick with the signal clock advanced by dt. Vehicles still move one tick's worth, so a
+    // headless caller can run the signal plan at any rate without waiting on the wall clock.
+    pub fn step(&mut self, dt: Duration) {
+        if self.perf.is_none() {
+            self.simulate_tick(dt);
+        } else {
+            let start = Instant::now();
+            self.simulate_tick(dt);
+            let elapsed = start.elapsed();
+            if let Some(perf) = &mut self.perf {
+                perf.record(elapsed);
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        self.validate_invariants();
+    }
+
+    // Debug-build self-check run after every update; panics describing the first broken invariant
+    #[cfg(debug_assertions)]
+    pub fn validate_invariants(&self) {
+        let margin = 2 * VEHICLE_SIZE as i32;
+        let active: Vec<&Vehicle> = self.vehicles.iter().filter(|v| !v.passed).collect();
+        for (i, v) in active.iter().enumerate() {
+            assert!(
+                self.vehicles.iter().filter(|other| other.id == v.id).count() == 1,
+                "tick {}: vehicle id {} is not unique",
+                self.ticks,
+                v.id
+            );
+            assert!(
+                v.path_index < v.path.len(),
+                "tick {}: vehicle {} has path_index {} on a path of {} points",
+                self.ticks,
+                v.id,
+                v.path_index,
+                v.path.len()
+            );
+            assert!(
+                (-margin..self.config.window_width as i32 + margin).contains(&v.x)
+                    && (-margin..self.config.window_height as i32 + margin).contains(&v.y),
+                "tick {}: vehicle {} is off the map at ({}, {})",
+                self.ticks,
+                v.id,
+                v.x,
+                v.y
+            );
+            assert!(
+                v.ticks_in_box != 1 || !self.pedestrians_crossing(),
+                "tick {}: vehicle {} entered the box while pedestrians were crossing",
+                self.ticks,
+                v.id
+            );
+            // Entering the box is only allowed on green, or for a permitted turn on red; vehicles
+            // already inside may clear on red
+            assert!(
+                v.ticks_in_box != 1 || self.controller.serves(v.dir) || (self.right_on_red && v.turn == self.config.curb_turn()),
+                "tick {}: vehicle {} from {:?} entered the box while {:?} was green",
+                self.ticks,
+                v.id,
+                v.dir,
+                self.controller.current
+            );
+            for other in &active[i + 1..] {
+                assert!(
+                    !v.overlaps(other),
+                    "tick {}: vehicles {} at ({}, {}) and {} at ({}, {}) overlap",
+                    self.ticks,
+                    v.id,
+                    v.x,
+                    v.y,
+                    other.id,
+                    other.x,
+                    other.y
+                );
+            }
+        }
+    }
+
+    // Headless driver: advance until pred holds or max_ticks have run, returning the ticks elapsed
+    pub fn run_until<F: Fn(&World) -> bool>(&mut self, pred: F, max_ticks: u64) -> u64 {
+        let mut elapsed = 0;
+        while elapsed < max_ticks && !pred(self) {
+            self.update();
+            elapsed += 1;
+        }
+        elapsed
+    }
+
+    fn simulate_tick(&mut self, dt: Duration) {
+        self.events.clear();
+        self.ticks += 1;
+        self.update_pedestrians();
+
+        // Flashing red needs the controller to keep counting idle time on an empty map
+        if self.vehicles.is_empty() && self.controller.flash_after().is_zero() {
             self.controller.current = Direction::AllRed;
+            self.all_red_trigger.clear();
             return;
         }
 
-        let waiting_vehicles = self.count_waiting_vehicles();
-        let cars_in_intersection = self.check_cars_in_intersection();
-        let vehicles_on_stop_line = self.check_vehicles_on_stop_line();
+        if self.controller.preempted().is_some_and(|dir| !self.emergency_pending(dir)) {
+            self.controller.clear_preemption();
+        }
 
-        self.controller.update(
-            waiting_vehicles,
-            cars_in_intersection,
-            vehicles_on_stop_line,
-            self.is_congested(self.controller.current),
-        );
+        let inputs = SignalInputs {
+            waiting_vehicles: self.count_waiting_vehicles(),
+            // Pedestrians on a crosswalk keep the box closed just like a vehicle inside it
+            cars_in_intersection: self.cars_in_intersection() || self.pedestrians_crossing(),
+            vehicles_on_stop_line: self.check_vehicles_on_stop_line(),
+            vehicle_approaching: self.vehicle_approaching_green(),
+            longest_wait: self.longest_waits(),
+            queue_lengths: Direction::APPROACHES.map(|dir| self.queue_length(dir)),
+        };
+
+        let previous_phase = self.controller.current;
+        let was_scramble = self.controller.is_scramble();
+        self.controller.update(dt, inputs);
+        if self.controller.is_scramble() && !was_scramble {
+            for dir in Direction::APPROACHES {
+                if !self.pedestrians.iter().any(|p| p.crossing == dir && !p.walking) {
+                    self.spawn_pedestrian(dir);
+                }
+            }
+        }
+        if self.controller.current == Direction::AllRed {
+            if previous_phase != Direction::AllRed {
+                self.all_red_trigger = self.vehicles_in_intersection();
+                self.events.push(SimEvent::AllRedEntered { vehicles: self.all_red_trigger.clone() });
+            }
+        } else {
+            self.all_red_trigger.clear();
+        }
 
         self.update_vehicle_positions();
+        let queues = Direction::APPROACHES.map(|dir| self.queue_length(dir));
+        self.metrics.record_queues(queues);
+        self.detect_collisions();
+        self.detect_near_misses();
+        self.track_dwell_times();
 
-        self.vehicles.retain(|v| {
-            v.x > -(VEHICLE_SIZE as i32)
-                && v.x < WINDOW_WIDTH as i32 + VEHICLE_SIZE as i32
-                && v.y > -(VEHICLE_SIZE as i32)
-                && v.y < WINDOW_HEIGHT as i32 + VEHICLE_SIZE as i32
-        });
+        self.retire_departed_vehicles();
     }
 
-    pub fn spawn_vehicle(&mut self, dir: Direction) {
-        if self.is_congested(dir) {
-            return;
+    // Marks vehicles leaving the screen as passed and drops them once their fade-out is over.
+    // With fading enabled they are retired at the window edge so the fade is visible.
+    fn retire_departed_vehicles(&mut self) {
+        for v in self.vehicles.iter_mut().filter(|v| v.passed) {
+            v.fade_remaining = v.fade_remaining.saturating_sub(1);
         }
 
-        let mut rng = rand::thread_rng();
-        let turn = match rng.gen_range(0..3) {
-            0 => Turn::Left,
-            1 => Turn::Right,
-            _ => Turn::Straight,
-        };
-        let path = generate_path(dir, turn);
-        let (x, y) = (path[0].0, path[0].1);
+        let wrap = self.boundary_mode == BoundaryMode::Wrap;
+        for i in 0..self.vehicles.len() {
+            let v = &self.vehicles[i];
+            if v.passed {
+                continue;
+            }
+            let exit = if self.fade_ticks > 0 && !wrap { v.edge_reached(&self.config) } else { v.exit_edge(&self.config) };
+            let Some(exit) = exit else { continue };
+
+            if wrap {
+                // Leaving through an edge means arriving from the opposite one, keeping the turn.
+                // A vehicle whose entry is still occupied waits off screen and tries again next tick.
+                let dir = exit.opposite();
+                let path = generate_path(&self.config, dir, v.turn);
+                if !self.entry_clear(dir, path[0], v.kind) {
+                    continue;
+                }
+                let v = &mut self.vehicles[i];
+                self.metrics.record_departure(v, self.ticks);
+                self.events.push(SimEvent::Departed { id: v.id, exit });
+                v.restart(dir, path, self.ticks);
+            } else {
+                let v = &mut self.vehicles[i];
+                self.metrics.record_departure(v, self.ticks);
+                self.events.push(SimEvent::Departed { id: v.id, exit });
+                v.passed = true;
+                v.fade_remaining = self.fade_ticks;
+            }
+        }
+
+        self.vehicles.retain(|v| !v.passed || v.fade_remaining > 0);
+    }
 
-        if let Some(last_vehicle) = self.vehicles.iter().filter(|v| v.dir == dir).last() {
-            let dist_sq = (x - last_vehicle.x).pow(2) + (y - last_vehicle.y).pow(2);
-            if dist_sq
-                < ((VEHICLE_SIZE + VEHICLE_SAFETY_GAP) * (VEHICLE_SIZE + VEHICLE_SAFETY_GAP)) as i32
-            {
-                return;
+    // True when a vehicle of kind entering from dir at point would keep the safety gap to every
+    // active vehicle in its lane; one in the lane beside it only has to be fully clear of it
+    fn entry_clear(&self, dir: Direction, point: (i32, i32), kind: VehicleKind) -> bool {
+        self.vehicles.iter().filter(|v| !v.passed).all(|v| {
+            // Squares share the front edge, so the longer body decides how far apart they must be
+            let gap = (v.kind.length().max(kind.length()) + VEHICLE_SAFETY_GAP) as i32;
+            let (dx, dy) = ((v.x - point.0).abs(), (v.y - point.1).abs());
+            let (along, beside) = match dir {
+                Direction::North | Direction::South => (dy, dx),
+                _ => (dx, dy),
+            };
+            along >= gap || beside >= VEHICLE_SIZE as i32
+        })
+    }
+
+    // Spawns arrivals for the current tick from a demand profile. Each approach gets a vehicle
+    // with probability equal to its rate, drawn from the seeded RNG so runs stay reproducible.
+    pub fn spawn_from_demand(&mut self, profile: &DemandProfile) {
+        for dir in Direction::APPROACHES {
+            let rate = profile.rate_at(self.ticks, dir).min(1.0);
+            if rate > 0.0 && self.rng.gen_bool(rate) {
+                self.spawn_vehicle(dir);
+            }
+        }
+    }
+
+    // Spawns on an approach chosen by random_direction, so a run replays exactly from its seed
+    pub fn spawn_random(&mut self) -> Option<u32> {
+        let dir = self.random_direction();
+        self.spawn_vehicle(dir)
+    }
+
+    // Returns the id of the new vehicle, or None when the approach has no room for it or the
+    // world already holds max_vehicles
+    pub fn spawn_vehicle(&mut self, dir: Direction) -> Option<u32> {
+        self.spawn_vehicle_with_speed(dir, None)
+    }
+
+    // Like spawn_vehicle, with max_speed overriding the world's cruise_speed for this vehicle
+    pub fn spawn_vehicle_with_speed(&mut self, dir: Direction, max_speed: Option<f32>) -> Option<u32> {
+        if !self.has_room(dir) {
+            return None;
+        }
+        let turn = self.spawn_distribution.sample_turn(dir, &mut self.rng);
+        let kind = self.spawn_distribution.sample_kind(&mut self.rng);
+        self.spawn_on_route(dir, turn, kind, max_speed)
+    }
+
+    // Like spawn_vehicle, with the turn chosen by the caller instead of the spawn distribution
+    pub fn spawn_vehicle_with_turn(&mut self, dir: Direction, turn: Turn) -> Option<u32> {
+        if !self.has_room(dir) {
+            return None;
+        }
+        let kind = self.spawn_distribution.sample_kind(&mut self.rng);
+        self.spawn_on_route(dir, turn, kind, None)
+    }
+
+    // Like spawn_vehicle, with the kind chosen by the caller instead of the spawn distribution
+    pub fn spawn_vehicle_of_kind(&mut self, dir: Direction, kind: VehicleKind) -> Option<u32> {
+        if !self.has_room(dir) {
+            return None;
+        }
+        let turn = self.spawn_distribution.sample_turn(dir, &mut self.rng);
+        self.spawn_on_route(dir, turn, kind, None)
+    }
+
+    // Brings in a vehicle arriving from a neighbouring intersection, keeping its id, kind and turn.
+    // Waits for room like a spawn; the caller keeps ids unique between worlds.
+    pub fn admit_vehicle(&mut self, id: u32, dir: Direction, turn: Turn, kind: VehicleKind) -> Option<u32> {
+        if !self.has_room(dir) {
+            return None;
+        }
+        let (x, y) = generate_path(&self.config, dir, turn)[0];
+        if !self.entry_clear(dir, (x, y), kind) {
+            return None;
+        }
+        self.place_vehicle(id, dir, turn, (x as f32, y as f32), 0)?;
+        if let Some(v) = self.vehicles.last_mut() {
+            v.set_kind(kind, self.cruise_speed);
+        }
+        Some(id)
+    }
+
+    // Below max_vehicles overall and the approach not backed up to its entry
+    fn has_room(&self, dir: Direction) -> bool {
+        self.vehicles.len() < self.max_vehicles && !self.is_congested(dir)
+    }
+
+    fn spawn_on_route(&mut self, dir: Direction, turn: Turn, kind: VehicleKind, max_speed: Option<f32>) -> Option<u32> {
+        let (x, y) = generate_path(&self.config, dir, turn)[0];
+        if !self.entry_clear(dir, (x, y), kind) {
+            return None;
+        }
+
+        let id = self.add_vehicle(dir, turn, (x as f32, y as f32), 0)?;
+        if let Some(v) = self.vehicles.last_mut() {
+            v.set_kind(kind, self.cruise_speed);
+            if let Some(speed) = max_speed {
+                v.speed = speed;
+                v.max_speed = speed;
             }
         }
+        Some(id)
+    }
+
+    // Spawns an emergency vehicle on dir and preempts the signal for it
+    pub fn spawn_emergency_vehicle(&mut self, dir: Direction) -> Option<u32> {
+        let id = self.spawn_vehicle(dir)?;
+        if let Some(v) = self.vehicles.last_mut() {
+            v.is_emergency = true;
+        }
+        self.request_preemption(dir);
+        Some(id)
+    }
 
+    // Switches to green for dir through AllRed and holds it while an emergency vehicle of dir
+    // has yet to clear the box; normal cycling resumes on its own after that
+    pub fn request_preemption(&mut self, dir: Direction) {
+        self.controller.preempt(dir);
+    }
+
+    // An emergency vehicle of dir still before or inside the box
+    fn emergency_pending(&self, dir: Direction) -> bool {
+        self.vehicles
+            .iter()
+            .any(|v| v.is_emergency && !v.passed && v.dir == dir && (v.path_index <= 1 || v.in_intersection(&self.config)))
+    }
+
+    // Places a vehicle on the route for dir and turn without any spacing checks, heading for
+    // waypoint path_index + 1. Used by spawning and to rebuild scenarios. None for AllRed.
+    pub fn add_vehicle(&mut self, dir: Direction, turn: Turn, pos: (f32, f32), path_index: usize) -> Option<u32> {
+        let id = self.next_id;
+        self.place_vehicle(id, dir, turn, pos, path_index)?;
+        self.next_id += 1;
+        Some(id)
+    }
+
+    // add_vehicle with the id given by the caller
+    fn place_vehicle(&mut self, id: u32, dir: Direction, turn: Turn, pos: (f32, f32), path_index: usize) -> Option<()> {
+        let path = generate_path(&self.config, dir, turn);
+        if path.is_empty() {
+            return None;
+        }
+        let path_index = path_index.min(path.len() - 2);
+        let heading = segment_heading(path[path_index], path[path_index + 1]);
         self.vehicles.push(Vehicle {
-            id: self.next_id,
+            id,
             dir,
             turn,
-            x,
-            y,
+            kind: VehicleKind::Car,
+            x: pos.0.round() as i32,
+            y: pos.1.round() as i32,
+            pos,
             path,
-            path_index: 0,
+            path_index,
+            speed: self.cruise_speed,
+            max_speed: self.cruise_speed,
+            ticks_in_box: 0,
+            stalled_ticks: 0,
+            line_stop_ticks: 0,
+            stop_count: 0,
+            wait_ticks: 0,
+            spawned_at: self.ticks,
+            queued_at: None,
+            is_emergency: false,
+            passed: false,
+            fade_remaining: 0,
+            heading,
         });
-        self.next_id += 1;
+        Some(())
+    }
+
+    // Clears the map and restarts the signal cycle and all counters, as if freshly created with
+    // the same seed and settings
+    pub fn reset(&mut self) {
+        self.vehicles.clear();
+        self.pedestrians.clear();
+        self.controller.reset();
+        self.next_id = 0;
+        self.next_pedestrian_id = 0;
+        self.ticks = 0;
+        self.metrics = Metrics::new();
+        self.rng = StdRng::seed_from_u64(self.seed);
+        if self.perf.is_some() {
+            self.perf = Some(PerfStats::new());
+        }
+        self.events.clear();
+        self.all_red_trigger.clear();
+        self.collisions = 0;
+        self.overlapping.clear();
+        self.near_misses = 0;
+        self.near_miss_log.clear();
+        self.close_pairs.clear();
+    }
+
+    // Full state as JSON, e.g. to attach a reproducible bug report
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("Cannot serialize world: {}", e))
+    }
+
+    // Restores a world saved with to_json. The RNG state is not saved, so random spawns
+    // after loading replay from the seed rather than continuing the original run.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut world: World = serde_json::from_str(json).map_err(|e| format!("Invalid world state: {}", e))?;
+        world.rng = StdRng::seed_from_u64(world.seed);
+        Ok(world)
+    }
+
+    // Live vehicles as an editable scenario; loading it into a fresh world puts them back in place
+    pub fn export_current_as_scenario(&self) -> Scenario {
+        let vehicles = self
+            .vehicles
+            .iter()
+            .filter(|v| !v.passed)
+            .map(|v| ScenarioVehicle { dir: v.dir, turn: v.turn, kind: v.kind, pos: v.pos, path_index: v.path_index })
+            .collect();
+        Scenario { vehicles }
+    }
+
+    pub fn load_scenario(&mut self, scenario: &Scenario) {
+        for v in &scenario.vehicles {
+            if self.add_vehicle(v.dir, v.turn, v.pos, v.path_index).is_none() {
+                continue;
+            }
+            if let Some(added) = self.vehicles.last_mut() {
+                added.set_kind(v.kind, self.cruise_speed);
+            }
+        }
     }
 
     pub fn is_congested(&self, dir: Direction) -> bool {
         let (lane_length, num_vehicles) = match dir {
             Direction::North | Direction::South => (
-                ROAD_Y,
+                self.config.road_y(),
                 self.vehicles.iter().filter(|v| v.dir == dir && v.path_index <= 1).count() as u32,
             ),
             Direction::East | Direction::West => (
-                ROAD_X,
+                self.config.road_x(),
                 self.vehicles.iter().filter(|v| v.dir == dir && v.path_index <= 1).count() as u32,
             ),
             Direction::AllRed => (0, 0),
         };
-        let capacity = lane_length / (VEHICLE_SIZE + VEHICLE_SAFETY_GAP);
+        // Two lanes per approach: through/right and left-turn
+        let capacity = 2 * lane_length / (VEHICLE_SIZE + VEHICLE_SAFETY_GAP);
         num_vehicles >= capacity
     }
-}
\ No newline at end of file
+}
+
+// "3.0" when every approach has the same maximum green, otherwise "N/S/E/W" values like "4.0/4.0/2.0/2.0"
+fn phase_summary(durations: [Duration; 4]) -> String {
+    if durations.iter().all(|&d| d == durations[0]) {
+        return format!("{:.1}", durations[0].as_secs_f32());
+    }
+    durations.map(|d| format!("{:.1}", d.as_secs_f32())).join("/")
+}
+
+// Placeholder for a deserialized World until from_json reseeds it
+#[cfg(feature = "serde")]
+fn unseeded_rng() -> StdRng {
+    StdRng::seed_from_u64(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Seeded world whose signal stays green for `green` alone, for as long as a test runs
+    fn world_green_for(green: Direction) -> World {
+        let mut world = World::with_seed(7);
+        world.controller.set_adaptive_timing(false);
+        world.controller.set_max_phase_duration(Duration::from_secs(600));
+        world.controller.set_start_phase(green);
+        world
+    }
+
+    // Places count vehicles before dir's stop line, the first on the line and the others queued
+    // behind it with room to spare
+    fn line_up(world: &mut World, dir: Direction, turn: Turn, count: i32) -> Vec<u32> {
+        let path = generate_path(world.config(), dir, turn);
+        let (start, stop) = (path[0], path[1]);
+        let back = ((start.0 - stop.0).signum(), (start.1 - stop.1).signum());
+        let spacing = (VEHICLE_SIZE + 2 * VEHICLE_SAFETY_GAP) as i32;
+        (0..count)
+            .map(|k| {
+                let pos = (stop.0 + back.0 * k * spacing, stop.1 + back.1 * k * spacing);
+                world.add_vehicle(dir, turn, (pos.0 as f32, pos.1 as f32), 0).unwrap()
+            })
+            .collect()
+    }
+
+    // Spawns on a random approach every few ticks, calling check after each update
+    fn run_random_traffic(world: &mut World, ticks: u64, mut check: impl FnMut(&World)) {
+        for tick in 0..ticks {
+            if tick % 15 == 0 {
+                world.spawn_random();
+            }
+            world.update();
+            check(world);
+        }
+    }
+
+    // Adds a vehicle at pos that never moves again, like a breakdown
+    fn frozen(world: &mut World, dir: Direction, turn: Turn, pos: (i32, i32), path_index: usize) -> u32 {
+        let id = world.add_vehicle(dir, turn, (pos.0 as f32, pos.1 as f32), path_index).unwrap();
+        let v = world.vehicles.last_mut().unwrap();
+        v.speed = 0.0;
+        v.max_speed = 0.0;
+        id
+    }
+
+    #[test]
+    fn braking_for_red_starts_within_reaction_distance() {
+        let mut world = world_green_for(Direction::East);
+        let id = world.spawn_vehicle_with_turn(Direction::North, Turn::Straight).unwrap();
+        loop {
+            let v = world.vehicles.iter().find(|v| v.id == id).unwrap();
+            let distance = v.distance_to_stop_line().expect("brakes before reaching the line");
+            world.update();
+            let v = world.vehicles.iter().find(|v| v.id == id).unwrap();
+            if v.speed < MAX_SPEED {
+                assert!(distance <= world.reaction_distance, "braked {} px from the line", distance);
+                assert_eq!(v.path_index, 0, "started braking before passing the stop line waypoint");
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn config_summary_includes_seed_and_strategy() {
+        let mut world = World::with_seed(1234);
+        world.controller = TrafficLightController::with_strategy(traffic_light::SignalStrategy::Actuated);
+        let summary = world.config_summary();
+        assert!(summary.contains("Seed: 1234"), "{}", summary);
+        assert!(summary.contains("Strategy: Actuated"), "{}", summary);
+    }
+
+    #[test]
+    fn update_timing_is_measured_once_enabled() {
+        let mut world = World::with_seed(3);
+        assert_eq!(world.last_update_micros(), 0.0);
+        world.set_perf_stats_enabled(true);
+        for dir in Direction::APPROACHES {
+            world.spawn_vehicle(dir);
+        }
+        for _ in 0..10 {
+            world.update();
+        }
+        assert!(world.last_update_micros() > 0.0);
+    }
+
+    #[test]
+    fn clearing_the_box_keeps_the_queues() {
+        let mut world = world_green_for(Direction::East);
+        let queued = line_up(&mut world, Direction::North, Turn::Straight, 3);
+        let lane = generate_path(world.config(), Direction::East, Turn::Straight)[0].1;
+        let box_x = world.config().intersection_x_start() as i32 + 30;
+        frozen(&mut world, Direction::East, Turn::Straight, (box_x, lane), 2);
+        for _ in 0..30 {
+            world.update();
+        }
+        assert!(world.cars_in_intersection());
+
+        world.clear_intersection();
+        assert!(!world.cars_in_intersection());
+        assert_eq!(world.controller.current, Direction::AllRed);
+        let remaining: Vec<u32> = world.vehicles.iter().map(|v| v.id).collect();
+        assert_eq!(remaining, queued);
+    }
+
+    #[test]
+    fn excessive_dwell_flags_only_a_stuck_vehicle() {
+        let mut world = World::with_seed(11);
+        run_random_traffic(&mut world, 3000, |world| {
+            assert!(
+                !world.events().iter().any(|e| matches!(e, SimEvent::ExcessiveDwell { .. })),
+                "{:?} at tick {}",
+                world.events(),
+                world.ticks()
+            );
+        });
+
+        let mut world = world_green_for(Direction::North);
+        let lane = generate_path(world.config(), Direction::North, Turn::Straight)[0].0;
+        let box_y = world.config().intersection_y_start() as i32 + 30;
+        let id = frozen(&mut world, Direction::North, Turn::Straight, (lane, box_y), 2);
+        let mut flagged = 0;
+        for _ in 0..=world.max_dwell_ticks + 10 {
+            world.update();
+            flagged += world.events().iter().filter(|&e| *e == SimEvent::ExcessiveDwell { id }).count();
+        }
+        assert_eq!(flagged, 1);
+    }
+
+    #[test]
+    fn run_until_stops_when_the_tenth_vehicle_has_passed() {
+        let setup = || {
+            let mut world = World::with_seed(5);
+            for dir in Direction::APPROACHES {
+                line_up(&mut world, dir, Turn::Straight, 3);
+            }
+            world
+        };
+        let mut world = setup();
+        let ticks = world.run_until(|w| w.passed_count() >= 10, 10_000);
+        assert!(ticks < 10_000);
+        assert_eq!(world.ticks(), ticks);
+        assert!(world.passed_count() >= 10);
+
+        let mut replay = setup();
+        for _ in 1..ticks {
+            replay.update();
+        }
+        assert!(replay.passed_count() < 10);
+    }
+
+    #[test]
+    fn all_red_reports_the_vehicles_holding_the_box() {
+        let mut world = World::with_seed(9);
+        // Without an amber the only way into AllRed is Rule 3, an occupied box at switch time
+        world.controller.set_yellow_duration(Duration::ZERO);
+        let lane = generate_path(world.config(), Direction::North, Turn::Straight)[0].0;
+        let box_y = world.config().intersection_y_start() as i32 + 30;
+        let stuck = frozen(&mut world, Direction::North, Turn::Straight, (lane, box_y), 2);
+        line_up(&mut world, Direction::East, Turn::Straight, 2);
+
+        world.run_until(|w| w.controller.current == Direction::AllRed, 1000);
+        assert_eq!(world.controller.current, Direction::AllRed);
+        assert_eq!(world.all_red_trigger(), [stuck]);
+        assert_eq!(world.all_red_trigger(), world.vehicles_in_intersection());
+        assert!(world.events().contains(&SimEvent::AllRedEntered { vehicles: vec![stuck] }));
+    }
+
+    #[test]
+    fn no_vehicle_stalls_across_seeded_runs() {
+        for seed in 0..8 {
+            let mut world = World::with_seed(seed);
+            if seed % 2 == 1 {
+                world.spawn_distribution.set_kind_weights([3, 1, 1]);
+            }
+            run_random_traffic(&mut world, 2000, |world| {
+                let stalled: Vec<&SimEvent> =
+                    world.events().iter().filter(|e| matches!(e, SimEvent::Stalled { .. })).collect();
+                assert!(stalled.is_empty(), "seed {} tick {}: {:?}", seed, world.ticks(), stalled);
+            });
+        }
+    }
+
+    #[test]
+    fn passed_vehicle_fades_for_fade_ticks_before_removal() {
+        let mut world = world_green_for(Direction::West);
+        world.fade_ticks = 5;
+        let lane = generate_path(world.config(), Direction::West, Turn::Straight)[0].1;
+        let id = world.add_vehicle(Direction::West, Turn::Straight, (700.0, lane as f32), 2).unwrap();
+        world.run_until(|w| w.events().iter().any(|e| matches!(e, SimEvent::Departed { .. })), 100);
+
+        // The departure frame and fade_ticks - 1 more at decreasing brightness
+        let mut levels = Vec::new();
+        while let Some(v) = world.vehicles.iter().find(|v| v.id == id) {
+            assert!(v.passed);
+            levels.push(v.fade_level(world.fade_ticks));
+            world.update();
+        }
+        assert_eq!(levels, [1.0, 0.8, 0.6, 0.4, 0.2]);
+    }
+
+    #[test]
+    fn stopping_at_red_and_then_in_congestion_counts_two_stops() {
+        let mut world = World::with_seed(2);
+        world.controller.set_start_phase(Direction::East);
+        let lane = generate_path(world.config(), Direction::North, Turn::Straight)[0].0;
+        let past_box = world.config().intersection_y_end() as i32 + 70;
+        frozen(&mut world, Direction::North, Turn::Straight, (lane, past_box), 2);
+        let id = world.spawn_vehicle_with_turn(Direction::North, Turn::Straight).unwrap();
+
+        for _ in 0..600 {
+            world.update();
+        }
+        let v = world.vehicles.iter().find(|v| v.id == id).unwrap();
+        assert_eq!(v.path_index, 2, "should be queued behind the breakdown past the box");
+        assert_eq!(v.stop_count, 2);
+    }
+
+    #[test]
+    fn demand_profile_spawns_more_in_its_heavy_phase() {
+        let profile = DemandProfile::parse("# light, then heavy\n0,500,north,0.02\n500,1000,north,0.2\n").unwrap();
+        let mut world = World::with_seed(6);
+        let mut spawned = [0; 2];
+        for _ in 0..1000 {
+            let phase = (world.ticks() / 500) as usize;
+            world.spawn_from_demand(&profile);
+            spawned[phase] += world.vehicles.len();
+            // Keep the entry free so every arrival the profile draws gets through
+            world.clear_vehicles();
+            world.update();
+        }
+        assert!(spawned[1] > 3 * spawned[0], "light {} heavy {}", spawned[0], spawned[1]);
+    }
+
+    #[test]
+    fn avoid_green_spawns_fewer_on_the_green_approach() {
+        let mut world = World::with_seed(8);
+        world.avoid_green = true;
+        assert!(world.controller.is_green(Direction::North));
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            counts[world.random_direction().approach_index().unwrap()] += 1;
+        }
+        // One in ten draws expected for North against three in ten for each red approach
+        for &red in &counts[1..] {
+            assert!(counts[0] * 2 < red, "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn queue_positions_follow_arrival_order() {
+        let mut world = world_green_for(Direction::East);
+        let mut arrivals = Vec::new();
+        for _ in 0..3 {
+            arrivals.push(world.spawn_vehicle_with_turn(Direction::North, Turn::Straight).unwrap());
+            for _ in 0..60 {
+                world.update();
+            }
+        }
+        for _ in 0..120 {
+            world.update();
+        }
+        let order = world.queue_order(Direction::North);
+        assert_eq!(order, arrivals);
+        for (position, id) in order.iter().enumerate() {
+            let v = world.vehicles.iter().find(|v| v.id == *id).unwrap();
+            assert!(v.queued_at.is_some(), "vehicle at position {} never queued", position + 1);
+        }
+        assert!(world.queue_order(Direction::South).is_empty());
+    }
+
+    #[test]
+    fn wrap_keeps_every_vehicle_in_play() {
+        let mut world = World::with_seed(21);
+        world.boundary_mode = BoundaryMode::Wrap;
+        for tick in 0..300 {
+            if tick % 30 == 0 {
+                world.spawn_random();
+            }
+            world.update();
+        }
+        let count = world.vehicles.len();
+        assert!(count >= 5, "only {} vehicles spawned", count);
+
+        let mut wrapped = 0;
+        for _ in 0..5000 {
+            world.update();
+            wrapped += world.events().iter().filter(|e| matches!(e, SimEvent::Departed { .. })).count();
+            assert_eq!(world.vehicles.len(), count);
+            assert!(world.vehicles.iter().all(|v| !v.passed));
+        }
+        assert!(wrapped > count, "only {} vehicles wrapped", wrapped);
+    }
+
+    #[test]
+    fn opposing_straights_share_a_paired_green_without_touching() {
+        let mut world = world_green_for(Direction::North);
+        world.controller.set_paired(true);
+        line_up(&mut world, Direction::North, Turn::Straight, 3);
+        line_up(&mut world, Direction::South, Turn::Straight, 3);
+
+        let mut crossed_together = false;
+        for _ in 0..400 {
+            world.update();
+            let live: Vec<&Vehicle> = world.vehicles.iter().filter(|v| !v.passed).collect();
+            let (southbound, northbound): (Vec<&Vehicle>, Vec<&Vehicle>) = live.iter().partition(|v| v.dir == Direction::North);
+            for a in &southbound {
+                for b in &northbound {
+                    assert!(!a.overlaps(b), "vehicles {} and {} touch at tick {}", a.id, b.id, world.ticks());
+                }
+            }
+            let in_box = |group: &[&Vehicle]| group.iter().any(|v| v.in_intersection(world.config()));
+            crossed_together |= in_box(&southbound) && in_box(&northbound);
+        }
+        assert!(crossed_together, "North and South never crossed at the same time");
+        assert!(world.vehicles.iter().all(|v| v.passed));
+        assert_eq!(world.collisions(), 0);
+    }
+
+    #[test]
+    fn scramble_holds_vehicles_and_walks_every_crosswalk() {
+        let mut world = World::with_seed(5);
+        world.controller.set_scramble(1, Duration::from_secs(3));
+        let mut scrambles = 0;
+        let mut walked = Vec::new();
+        let mut was_scramble = false;
+        run_random_traffic(&mut world, 3000, |world| {
+            let scramble = world.controller.is_scramble();
+            if scramble {
+                scrambles += !was_scramble as u32;
+                let entered = world.vehicles.iter().find(|v| v.ticks_in_box == 1);
+                assert!(entered.is_none(), "vehicle entered the box during the scramble at tick {}: {:?}", world.ticks(), entered.map(|v| v.id));
+                for p in world.pedestrians.iter().filter(|p| p.walking) {
+                    if !walked.contains(&p.crossing) {
+                        walked.push(p.crossing);
+                    }
+                }
+            }
+            was_scramble = scramble;
+        });
+        assert!(scrambles >= 2, "only {} scrambles", scrambles);
+        for dir in Direction::APPROACHES {
+            assert!(walked.contains(&dir), "nobody walked across the {:?} arm", dir);
+        }
+    }
+
+    #[test]
+    fn exported_scenario_reloads_the_same_vehicles() {
+        let mut world = World::with_seed(13);
+        run_random_traffic(&mut world, 400, |_| {});
+        let exported = world.export_current_as_scenario();
+        assert!(exported.vehicles.len() >= 5, "only {} vehicles to export", exported.vehicles.len());
+
+        // Through the editable text form, as a saved file would be
+        let reparsed = Scenario::parse(&exported.to_text()).unwrap();
+        let mut restored = World::with_seed(99);
+        restored.load_scenario(&reparsed);
+        assert_eq!(restored.export_current_as_scenario(), exported);
+        let positions = |world: &World| world.vehicles.iter().map(|v| (v.x, v.y, v.heading())).collect::<Vec<_>>();
+        assert_eq!(positions(&restored), positions(&world));
+    }
+
+    #[test]
+    fn headway_spreads_fast_pairs_more_than_slow_ones() {
+        // Mean bumper-to-bumper gap of a follower released right behind its leader on a green arm
+        let mean_gap = |max_speed: f32| {
+            let mut world = world_green_for(Direction::East);
+            world.headway_ticks = 10.0;
+            let lane = generate_path(world.config(), Direction::East, Turn::Straight)[0].1;
+            let leader = world.add_vehicle(Direction::East, Turn::Straight, (780.0, lane as f32), 0).unwrap();
+            let follower = world.add_vehicle(Direction::East, Turn::Straight, (810.0, lane as f32), 0).unwrap();
+            for v in &mut world.vehicles {
+                v.max_speed = max_speed;
+                v.speed = max_speed;
+            }
+            let mut gaps = Vec::new();
+            for tick in 0..100 {
+                world.update();
+                if tick >= 60 {
+                    let x = |id: u32| world.vehicles.iter().find(|v| v.id == id).unwrap().pos.0;
+                    gaps.push(x(follower) - x(leader) - VEHICLE_SIZE as f32);
+                }
+            }
+            gaps.iter().sum::<f32>() / gaps.len() as f32
+        };
+        let (fast, slow) = (mean_gap(MAX_SPEED), mean_gap(1.5));
+        assert!(fast > slow + 20.0, "fast pair gap {} vs slow pair gap {}", fast, slow);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn healthy_run_passes_validation() {
+        let mut world = World::with_seed(17);
+        run_random_traffic(&mut world, 2000, |world| world.validate_invariants());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "is not unique")]
+    fn duplicate_vehicle_trips_validation() {
+        let mut world = World::with_seed(17);
+        world.spawn_vehicle(Direction::West);
+        let twin = world.vehicles[0].clone();
+        world.vehicles.push(twin);
+        world.validate_invariants();
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "has path_index")]
+    fn path_index_past_the_route_trips_validation() {
+        let mut world = World::with_seed(17);
+        world.spawn_vehicle(Direction::West);
+        world.vehicles[0].path_index = world.vehicles[0].path.len();
+        world.validate_invariants();
+    }
+
+    #[test]
+    fn lone_car_clearing_does_not_cut_the_green_short() {
+        let min_green = Duration::from_secs(2);
+        let mut world = World::with_seed(8);
+        world.controller.set_min_green(min_green);
+        world.controller.set_max_phase_duration(Duration::from_secs(10));
+        let id = world.spawn_vehicle_with_turn(Direction::North, Turn::Straight).unwrap();
+
+        let mut green_ticks = 0;
+        while world.controller.phase() == traffic_light::Phase::Green(Direction::North) {
+            world.update();
+            green_ticks += 1;
+            assert!(green_ticks < 1000, "North never left green");
+        }
+        let v = world.vehicles.iter().find(|v| v.id == id);
+        assert!(v.is_none_or(|v| v.path_index >= 2), "the car had not cleared the line");
+        let held = TICK_DURATION * green_ticks;
+        assert!(held >= min_green, "green lasted only {:?}", held);
+        assert!(held < min_green + Duration::from_secs(1), "green held {:?}, not ended by the empty approach", held);
+    }
+
+    #[test]
+    fn same_lane_straights_never_overlap() {
+        let mut world = World::with_seed(6);
+        let mut ids = Vec::new();
+        for tick in 0..1500 {
+            if tick % 10 == 0 && ids.len() < 12 {
+                ids.extend(world.spawn_vehicle_with_turn(Direction::West, Turn::Straight));
+            }
+            world.update();
+            let live: Vec<&Vehicle> = world.vehicles.iter().filter(|v| !v.passed).collect();
+            for (i, a) in live.iter().enumerate() {
+                for b in &live[i + 1..] {
+                    assert!(!a.overlaps(b), "vehicles {} and {} overlap at tick {}", a.id, b.id, world.ticks());
+                }
+            }
+        }
+        assert!(ids.len() >= 8, "only {} vehicles spawned", ids.len());
+        assert!(world.vehicles.iter().all(|v| v.passed || !ids.contains(&v.id)), "not every vehicle got through");
+        assert_eq!(world.collisions(), 0);
+    }
+
+    #[test]
+    fn right_turner_clears_on_red_when_allowed() {
+        let cleared_on_red = |right_on_red: bool| {
+            let mut world = world_green_for(Direction::East);
+            world.right_on_red = right_on_red;
+            let id = line_up(&mut world, Direction::North, Turn::Right, 1)[0];
+            for _ in 0..400 {
+                world.update();
+                assert!(!world.controller.serves(Direction::North));
+                if world.vehicles.iter().find(|v| v.id == id).is_none_or(|v| v.passed) {
+                    return true;
+                }
+            }
+            false
+        };
+        assert!(cleared_on_red(true), "right-turner never cleared on red");
+        assert!(!cleared_on_red(false), "right-turner went on red without right_on_red");
+    }
+
+    #[test]
+    fn jammed_exit_keeps_cars_out_of_the_box() {
+        let mut world = world_green_for(Direction::North);
+        let lane = generate_path(world.config(), Direction::North, Turn::Straight)[0].0;
+        let exit_start = world.config().intersection_y_end() as i32 + 5;
+        let spacing = (VEHICLE_SIZE + VEHICLE_SAFETY_GAP) as i32;
+        for k in 0..4 {
+            frozen(&mut world, Direction::North, Turn::Straight, (lane, exit_start + k * spacing), 3);
+        }
+        let queued = line_up(&mut world, Direction::North, Turn::Straight, 3);
+
+        for _ in 0..300 {
+            world.update();
+            for v in world.vehicles.iter().filter(|v| queued.contains(&v.id)) {
+                assert!(!v.in_intersection(world.config()), "vehicle {} entered the box at tick {}", v.id, world.ticks());
+            }
+        }
+        let front = world.vehicles.iter().find(|v| v.id == queued[0]).unwrap();
+        assert_eq!(front.path_index, 1, "front car left its stop line");
+    }
+}
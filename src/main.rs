@@ -1,24 +1,123 @@
-use rand::Rng;
-use road_intersection::{Direction, Turn, World, WINDOW_WIDTH, WINDOW_HEIGHT, ROAD_WIDTH, ROAD_X, ROAD_Y, INTERSECTION_X_START, INTERSECTION_Y_START, INTERSECTION_X_END, INTERSECTION_Y_END, SOUTHBOUND_LANE_X, NORTHBOUND_LANE_X, WESTBOUND_LANE_Y, EASTBOUND_LANE_Y};
+use road_intersection::{BoundaryMode, Config, Direction, Turn, World, WINDOW_WIDTH, WINDOW_HEIGHT, ROAD_WIDTH, VEHICLE_SIZE, TICK_DURATION};
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use sdl2::render::{Canvas, TextureCreator};
 use sdl2::video::{Window, WindowContext};
 use sdl2::ttf::Font;
-use road_intersection::vehicle::Vehicle;
+use road_intersection::ascii::{self, Renderer, choose_renderer};
+use road_intersection::camera::{Camera, PAN_STEP, ZOOM_STEP};
+use road_intersection::capture::{Frame, GifCapture};
+use road_intersection::controls::{ArrowKey, KeymapMode, click_direction, spawn_direction};
+use road_intersection::demand::DemandProfile;
+use road_intersection::kinematics::PhysicalScale;
+use road_intersection::layout::{QUEUE_BADGE_SIZE, SIGNAL_SIZE, countdown_position, lane_arrow, queue_badge_position, signal_position};
+use road_intersection::metrics::{WAIT_HISTOGRAM_BINS, bar_height};
+use road_intersection::pedestrian::{CROSSWALK_WIDTH, PEDESTRIAN_SIZE, Pedestrian, crosswalk};
+use road_intersection::scenario::Scenario;
+use road_intersection::traffic_light::{MAX_WEIGHTED_GREEN, SCRAMBLE_DURATION, SignalColor, TrafficLightController};
+use road_intersection::vehicle::{Vehicle, path_segments};
 
 const SPAWN_TIMEOUT: Duration = Duration::from_millis(250);
+const BATCH_TICKS: u64 = 10_000; // Default length of a --batch run
+const SCENARIO_EXPORT_PATH: &str = "scenario.csv"; // Written by the E key
+const SCRAMBLE_EVERY_CYCLES: u32 = 2; // Cycles between pedestrian scrambles once enabled with S
+const GIF_DOWNSCALE: u16 = 2; // Default shrink factor for recorded frames, --gif-downscale overrides it
+const GIF_TICK_INTERVAL: u64 = 2; // Default ticks per recorded frame, --gif-every overrides it
 
 fn main() -> Result<(), String> {
-    let sdl = sdl2::init()?;
-    let video = sdl.video()?;
-    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let config = Config::default()
+        .with_window(
+            parse_arg::<u32>("--window-width")?.unwrap_or(WINDOW_WIDTH),
+            parse_arg::<u32>("--window-height")?.unwrap_or(WINDOW_HEIGHT),
+        )
+        .with_road_width(parse_arg::<u32>("--road-width")?.unwrap_or(ROAD_WIDTH))
+        .with_drive_on_left(has_flag("--drive-on-left"));
+    config.validate()?;
+    let mut world = match parse_arg::<u64>("--seed")? {
+        Some(seed) => World::with_seed_and_config(seed, config),
+        None => World::with_config(config),
+    };
+    if let Some(fade_ticks) = parse_arg::<u32>("--fade-ticks")? {
+        world.fade_ticks = fade_ticks;
+    }
+    if let Some(max_vehicles) = parse_arg::<usize>("--max-vehicles")? {
+        world.max_vehicles = max_vehicles;
+    }
+    if let Some(follow_distance) = parse_arg::<u32>("--follow-distance")? {
+        world.follow_distance = follow_distance;
+    }
+    if let Some(near_miss_distance) = parse_arg::<u32>("--near-miss-distance")? {
+        world.near_miss_distance = near_miss_distance;
+    }
+    if let Some(headway_ticks) = parse_arg::<f32>("--headway-ticks")? {
+        world.headway_ticks = headway_ticks;
+    }
+    if let Some(list) = parse_arg::<String>("--phase-durations")? {
+        world.controller.set_phase_durations(parse_phase_durations(&list)?);
+    }
+    if let Some(list) = parse_arg::<String>("--vehicle-mix")? {
+        world.spawn_distribution.set_kind_weights(parse_vehicle_mix(&list)?);
+    }
+    if let Some(seconds) = parse_arg::<f32>("--phase")? {
+        let duration = Duration::try_from_secs_f32(seconds).map_err(|e| format!("invalid value '{}' for --phase: {}", seconds, e))?;
+        world.controller.set_max_phase_duration(duration);
+    }
+    if let Some(seconds) = parse_arg::<f32>("--flash-after")? {
+        let idle = Duration::try_from_secs_f32(seconds).map_err(|e| format!("invalid value '{}' for --flash-after: {}", seconds, e))?;
+        world.controller.set_flashing_red(idle);
+    }
+    if let Some(seconds) = parse_arg::<f32>("--green-per-vehicle")? {
+        let per_vehicle = Duration::try_from_secs_f32(seconds)
+            .map_err(|e| format!("invalid value '{}' for --green-per-vehicle: {}", seconds, e))?;
+        world.controller.set_queue_weighted_green(per_vehicle, MAX_WEIGHTED_GREEN);
+    }
+    if let Some(cap) = parse_arg::<usize>("--phase-history")? {
+        world.controller.set_phase_history(cap);
+    }
+    if has_flag("--start-all-red") {
+        world.controller.set_start_phase(Direction::AllRed);
+    }
+    if has_flag("--paired") {
+        world.controller.set_paired(true);
+    }
+    if has_flag("--right-on-red") {
+        world.right_on_red = true;
+    }
+    if has_flag("--wrap") {
+        world.boundary_mode = BoundaryMode::Wrap;
+    }
+    if let Some(path) = parse_arg::<String>("--scenario")? {
+        world.load_scenario(&Scenario::from_file(&path)?);
+    }
+    let demand = match parse_arg::<String>("--demand")? {
+        Some(path) => Some(DemandProfile::from_file(&path)?),
+        None => None,
+    };
+
+    if has_flag("--batch") {
+        let ticks = parse_arg::<u64>("--ticks")?.unwrap_or(BATCH_TICKS);
+        let spawn_every = parse_arg::<u64>("--spawn-every")?.unwrap_or(spawn_interval_ticks());
+        return run_batch(world, demand.as_ref(), ticks, spawn_every);
+    }
+
+    let renderer = choose_renderer(has_flag("--headless"), display_available()).unwrap_or_else(|e| exit_with(&e));
+    if renderer == Renderer::Ascii {
+        return run_headless(world, demand.as_ref(), parse_arg::<u64>("--ticks")?);
+    }
+
+    let sdl = sdl2::init().unwrap_or_else(|e| exit_with(&format!("could not start SDL: {}", e)));
+    let video = sdl
+        .video()
+        .unwrap_or_else(|e| exit_with(&format!("no display found ({}); try headless mode with --headless", e)));
+    let ttf_context = sdl2::ttf::init()
+        .unwrap_or_else(|e| exit_with(&format!("could not start SDL_ttf ({}); try headless mode with --headless", e)));
 
     let window = video
-        .window("Road Intersection", WINDOW_WIDTH, WINDOW_HEIGHT)
+        .window("Road Intersection", config.window_width, config.window_height)
         .position_centered()
         .build()
         .map_err(|e| e.to_string())?;
@@ -32,7 +131,7 @@ fn main() -> Result<(), String> {
 
     // Create a texture for the static background
     let mut static_background = texture_creator
-        .create_texture_target(None, WINDOW_WIDTH, WINDOW_HEIGHT)
+        .create_texture_target(None, config.window_width, config.window_height)
         .map_err(|e| e.to_string())?;
 
     // Draw the static elements to the new texture
@@ -42,16 +141,46 @@ fn main() -> Result<(), String> {
         texture_canvas.clear();
 
         // Draw all the static parts
-        draw_roads(texture_canvas).unwrap();
-        draw_lanes(texture_canvas).unwrap();
-        draw_lane_dividers(texture_canvas).unwrap();
-        draw_intersection_elements(texture_canvas).unwrap();
+        draw_roads(texture_canvas, &config).unwrap();
+        draw_lanes(texture_canvas, &config).unwrap();
+        draw_lane_dividers(texture_canvas, &config).unwrap();
     }).map_err(|e| e.to_string())?;
 
     let mut event_pump = sdl.event_pump()?;
-    let mut world = World::new();
+    let gif_downscale = parse_arg::<u16>("--gif-downscale")?.unwrap_or(GIF_DOWNSCALE);
+    let gif_tick_interval = parse_arg::<u64>("--gif-every")?.unwrap_or(GIF_TICK_INTERVAL);
+    let mut gif_path = parse_arg::<String>("--record-gif")?;
+    let mut gif_capture = match &gif_path {
+        Some(_) => Some(
+            GifCapture::new(
+                parse_arg::<u64>("--record-from")?.unwrap_or(0),
+                parse_arg::<u64>("--record-to")?.unwrap_or(600),
+            )
+            .with_sampling(gif_downscale, gif_tick_interval),
+        ),
+        None => None,
+    };
     let mut last_spawn_time = Instant::now();
     let mut random_generation_on = false;
+    let mut show_paths = false;
+    let mut show_ids = false; // Id labels cost a text render per vehicle, so they are off by default
+    let mut keymap_mode = KeymapMode::TravelHeading;
+    let mut show_dilemma_zone = false;
+    let mut show_markings = true;
+    let mut show_wait_histogram = false;
+    let mut show_lane_arrows = false;
+    let mut inspected: Option<u32> = None; // Vehicle shown in the inspector panel
+    let mut last_frame = Instant::now();
+    let mut frame: u64 = 0; // Rendered frames, drives blinking turn signals
+    let mut fps = 0.0f32; // Exponentially smoothed frame rate
+    let mut trend_sample = (Instant::now(), 0usize); // Time and vehicle count of the last trend sample
+    let mut vehicle_trend = 0i64; // Change in vehicle count over the last second
+    let mut forced_turn: Option<Turn> = None; // Turn for the next manual spawn, random when None
+    let mut screenshot_requested = false; // Set by F12, saved once the frame is drawn
+    let mut camera = Camera::new(config.window_width, config.window_height);
+    let mut mouse_position = (0, 0); // Last known pointer position in screen pixels, anchors wheel zoom
+    let mut dragging = false; // Middle button held: mouse motion pans the view
+    let mut paused = false; // Frozen frame: events and drawing continue, the world does not advance
 
     // Load font for overlay
     let font = ttf_context.load_font("assets/fonts/DejaVuSans.ttf", 12)?;
@@ -60,63 +189,221 @@ fn main() -> Result<(), String> {
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit { .. } => break 'running,
+                // A click on a vehicle inspects it; anywhere else spawns on the nearest approach
+                Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+                    let (x, y) = camera.to_world((x, y));
+                    inspected = vehicle_at(&world, x, y);
+                    if inspected.is_none() {
+                        handle_spawn_key(&mut world, &mut last_spawn_time, click_direction(&config, x, y), &mut forced_turn);
+                    }
+                }
+                Event::MouseButtonDown { mouse_btn: MouseButton::Middle, .. } => dragging = true,
+                Event::MouseButtonUp { mouse_btn: MouseButton::Middle, .. } => dragging = false,
+                Event::MouseMotion { x, y, xrel, yrel, .. } => {
+                    mouse_position = (x, y);
+                    if dragging {
+                        camera.pan(-xrel as f32, -yrel as f32);
+                    }
+                }
+                Event::MouseWheel { y, .. } => camera.zoom_at(ZOOM_STEP.powi(y), mouse_position),
+                // Shift+WASD pans; the plain letters keep their toggles
+                Event::KeyDown { keycode: Some(key @ (Keycode::W | Keycode::A | Keycode::S | Keycode::D)), keymod, .. }
+                    if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) =>
+                {
+                    let (dx, dy) = match key {
+                        Keycode::W => (0.0, -PAN_STEP),
+                        Keycode::S => (0.0, PAN_STEP),
+                        Keycode::A => (-PAN_STEP, 0.0),
+                        _ => (PAN_STEP, 0.0),
+                    };
+                    camera.pan(dx, dy);
+                }
                 Event::KeyDown { keycode, .. } => match keycode {
+                    Some(Keycode::Plus) | Some(Keycode::Equals) | Some(Keycode::KpPlus) => {
+                        camera.zoom_at(ZOOM_STEP, config.center());
+                    }
+                    Some(Keycode::Minus) | Some(Keycode::KpMinus) => {
+                        camera.zoom_at(1.0 / ZOOM_STEP, config.center());
+                    }
+                    Some(Keycode::Home) => camera = Camera::new(config.window_width, config.window_height),
                     Some(Keycode::Escape) => break 'running,
-                    Some(Keycode::Up) => handle_spawn_key(&mut world, &mut last_spawn_time, Direction::South),
-                    Some(Keycode::Down) => handle_spawn_key(&mut world, &mut last_spawn_time, Direction::North),
-                    Some(Keycode::Left) => handle_spawn_key(&mut world, &mut last_spawn_time, Direction::East),
-                    Some(Keycode::Right) => handle_spawn_key(&mut world, &mut last_spawn_time, Direction::West),
-                    Some(Keycode::R) => {
-                        let mut rng = rand::thread_rng();
-                        let random_dir = match rng.gen_range(0..4) {
-                            0 => Direction::North,
-                            1 => Direction::South,
-                            2 => Direction::East,
-                            _ => Direction::West,
+                    Some(Keycode::Space) => paused = !paused,
+                    // One tick per press while paused; update always advances the clock by TICK_DURATION
+                    Some(Keycode::N) | Some(Keycode::Period) if paused => world.update(),
+                    Some(Keycode::Up) => handle_spawn_key(&mut world, &mut last_spawn_time, spawn_direction(ArrowKey::Up, keymap_mode), &mut forced_turn),
+                    Some(Keycode::Down) => handle_spawn_key(&mut world, &mut last_spawn_time, spawn_direction(ArrowKey::Down, keymap_mode), &mut forced_turn),
+                    Some(Keycode::Left) => handle_spawn_key(&mut world, &mut last_spawn_time, spawn_direction(ArrowKey::Left, keymap_mode), &mut forced_turn),
+                    Some(Keycode::Right) => handle_spawn_key(&mut world, &mut last_spawn_time, spawn_direction(ArrowKey::Right, keymap_mode), &mut forced_turn),
+                    Some(Keycode::K) => keymap_mode = keymap_mode.toggled(),
+                    Some(Keycode::Z) => show_dilemma_zone = !show_dilemma_zone,
+                    Some(Keycode::H) => show_markings = !show_markings,
+                    Some(Keycode::V) => world.avoid_green = !world.avoid_green,
+                    Some(Keycode::W) => show_wait_histogram = !show_wait_histogram,
+                    Some(Keycode::A) => show_lane_arrows = !show_lane_arrows,
+                    Some(Keycode::F12) => screenshot_requested = true,
+                    // Starts an open-ended recording, or stops the current one and writes it out
+                    Some(Keycode::F11) => match &mut gif_capture {
+                        Some(capture) => capture.stop(world.ticks()),
+                        None => {
+                            gif_capture = Some(GifCapture::new(world.ticks(), u64::MAX).with_sampling(gif_downscale, gif_tick_interval));
+                            gif_path = Some(format!("recording-{}.gif", unix_seconds()?));
+                        }
+                    },
+                    Some(Keycode::E) => {
+                        let scenario = world.export_current_as_scenario();
+                        // Like a screenshot, a failed write is only reported so the run goes on
+                        match scenario.write_to_file(SCENARIO_EXPORT_PATH) {
+                            Ok(()) => println!("Wrote {} vehicles to {}", scenario.vehicles.len(), SCENARIO_EXPORT_PATH),
+                            Err(e) => eprintln!("Scenario export failed: {}", e),
+                        }
+                    }
+                    Some(Keycode::S) => {
+                        let every = if world.controller.scramble_every() > 0 { 0 } else { SCRAMBLE_EVERY_CYCLES };
+                        world.controller.set_scramble(every, SCRAMBLE_DURATION);
+                    }
+                    Some(Keycode::P) => {
+                        let scale = match world.physical_scale() {
+                            Some(_) => None,
+                            None => Some(PhysicalScale::standard()),
                         };
-                        handle_spawn_key(&mut world, &mut last_spawn_time, random_dir);
+                        world.set_physical_scale(scale);
+                    }
+                    Some(Keycode::R) => {
+                        let random_dir = world.random_direction();
+                        handle_spawn_key(&mut world, &mut last_spawn_time, random_dir, &mut forced_turn);
+                    }
+                    Some(Keycode::Num7) => forced_turn = Some(Turn::Left),
+                    Some(Keycode::Num8) => forced_turn = Some(Turn::Straight),
+                    Some(Keycode::Num9) => forced_turn = Some(Turn::Right),
+                    Some(Keycode::Num0) => forced_turn = None,
+                    Some(Keycode::M) => {
+                        let dir = world.random_direction();
+                        world.spawn_emergency_vehicle(dir);
+                    }
+                    Some(Keycode::C) => {
+                        world.reset();
+                        inspected = None;
                     }
                     Some(Keycode::G) => random_generation_on = !random_generation_on,
+                    Some(Keycode::D) => show_paths = !show_paths,
+                    Some(Keycode::I) => show_ids = !show_ids,
+                    Some(Keycode::T) => world.clear_intersection(),
+                    Some(Keycode::X) => world.clear_vehicles(),
+                    Some(Keycode::Num1) => world.controller.request_green(Direction::North),
+                    Some(Keycode::Num2) => world.controller.request_green(Direction::South),
+                    Some(Keycode::Num3) => world.controller.request_green(Direction::East),
+                    Some(Keycode::Num4) => world.controller.request_green(Direction::West),
+                    Some(Keycode::F) => {
+                        let enabled = world.perf_stats().is_none();
+                        world.set_perf_stats_enabled(enabled);
+                    }
                     _ => {}
                 },
                 _ => {}
             }
         }
 
-        if random_generation_on && last_spawn_time.elapsed() >= SPAWN_TIMEOUT {
-            let mut rng = rand::thread_rng();
-            let random_dir = match rng.gen_range(0..4) {
-                0 => Direction::North,
-                1 => Direction::South,
-                2 => Direction::East,
-                _ => Direction::West,
-            };
-            handle_spawn_key(&mut world, &mut last_spawn_time, random_dir);
+        if !paused {
+            if random_generation_on && last_spawn_time.elapsed() >= SPAWN_TIMEOUT {
+                let random_dir = world.random_direction();
+                handle_spawn_key(&mut world, &mut last_spawn_time, random_dir, &mut None);
+            }
+
+            if let Some(profile) = &demand {
+                world.spawn_from_demand(profile);
+            }
+
+            // Update simulation
+            world.update();
         }
 
-        // Update simulation
-        world.update();
+        // Copy the pre-rendered background, then draw the world through the camera
+        let (x, y, width, height) = camera.to_screen_rect(0, 0, config.window_width, config.window_height);
+        canvas.copy(&static_background, None, Some(Rect::new(x, y, width, height)))?;
+        let mut view = WorldView { canvas: &mut canvas, camera };
 
-        // Copy the pre-rendered background
-        canvas.copy(&static_background, None, None)?;
+        // Markings are purely visual (stop points live in the vehicle paths), so they can be hidden for screenshots
+        if show_markings {
+            draw_intersection_elements(&mut view, &config)?;
+            draw_crosswalks(&mut view, &config)?;
+        }
+        if show_lane_arrows {
+            draw_lane_arrows(&mut view, &config)?;
+        }
 
         // Draw dynamic elements
-        draw_traffic_lights(&mut canvas, &world.controller.current)?;
-        draw_vehicles(&mut canvas, &world.vehicles)?;
+        draw_traffic_lights(&mut view, &config, &world.controller)?;
+        draw_signal_countdowns(&mut view, &font, &texture_creator, &config, &world.controller)?;
+        if world.controller.is_scramble() {
+            draw_scramble(&mut view, &config)?;
+        }
+        if show_paths {
+            draw_vehicle_paths(&mut view, &world.vehicles)?;
+        }
+        draw_vehicles(&mut view, &config, &world.vehicles, world.fade_ticks, frame)?;
+        draw_pedestrians(&mut view, &world.pedestrians)?;
+        draw_all_red_trigger(&mut view, &world)?;
+        if show_dilemma_zone {
+            draw_dilemma_zone(&mut view, &world)?;
+        }
+        draw_queue_badges(&mut view, &font, &texture_creator, &world)?;
+        if show_paths {
+            draw_queue_order(&mut view, &font, &texture_creator, &world)?;
+        }
+        if show_ids {
+            draw_vehicle_ids(&mut view, &font, &texture_creator, &world.vehicles)?;
+        }
+
+        let frame_time = last_frame.elapsed().as_secs_f32();
+        last_frame = Instant::now();
+        frame += 1;
+        if frame_time > 0.0 {
+            fps = if fps == 0.0 { 1.0 / frame_time } else { fps * 0.9 + 0.1 / frame_time };
+        }
+        if trend_sample.0.elapsed() >= Duration::from_secs(1) {
+            vehicle_trend = world.vehicles.len() as i64 - trend_sample.1 as i64;
+            trend_sample = (Instant::now(), world.vehicles.len());
+        }
 
         // Overlay: show variables
         let overlay_text = format!(
-            "Vehicles: {}",
-            world.vehicles.len()
+            "Vehicles: {} / {} ({:+}/s), Collisions: {}, Near misses: {}, FPS: {:.0}",
+            world.vehicles.len(),
+            world.max_vehicles,
+            vehicle_trend,
+            world.collisions(),
+            world.near_misses(),
+            fps
         );
         render_text_overlay(&mut canvas, &font, &texture_creator, &overlay_text, 10, 10)?;
+        if paused {
+            render_text_overlay(&mut canvas, &font, &texture_creator, "PAUSED (Space), step (N)", config.center().0 - 70, 10)?;
+        }
+        if gif_capture.as_ref().is_some_and(|capture| capture.is_recording(world.ticks())) {
+            render_text_overlay(&mut canvas, &font, &texture_creator, "REC (F11)", config.window_width as i32 - 90, 10)?;
+        }
 
-        let random_gen_text = format!("Random Generation (G): {}", if random_generation_on { "ON" } else { "OFF" });
+        let random_gen_text = format!(
+            "Random Generation (G): {}, Avoid green (V): {}",
+            if random_generation_on { "ON" } else { "OFF" },
+            if world.avoid_green { "ON" } else { "OFF" },
+        );
         render_text_overlay(&mut canvas, &font, &texture_creator, &random_gen_text, 10, 35)?;
 
 
+        let keymap_text = format!(
+            "Arrow keys (K): {}, Real scale (P): {}, Next turn (7/8/9/0): {}",
+            match keymap_mode {
+                KeymapMode::TravelHeading => "direction of travel",
+                KeymapMode::ScreenEdge => "entry edge",
+            },
+            if world.physical_scale().is_some() { "ON" } else { "OFF" },
+            forced_turn.map_or("random".to_string(), |turn| format!("{:?}", turn)),
+        );
+        render_text_overlay(&mut canvas, &font, &texture_creator, &keymap_text, 10, 60)?;
+
         // New: Static Info Overlay (Colors and Directions)
-        let mut y_offset = 60; // Starting Y position for info, below the vehicle count
+        let mut y_offset = 85; // Starting Y position for info, below the vehicle count
 
         // Colors and Turns Legend
         let colors_legend_title = "Vehicle Colors (Turn):";
@@ -138,123 +425,623 @@ fn main() -> Result<(), String> {
             y_offset += 20;
         }
 
+        if let Some(perf) = world.perf_stats() {
+            let perf_text = format!("Update (F): {:.1} us (avg {:.1} us)", perf.last_micros(), perf.average_micros());
+            render_text_overlay(&mut canvas, &font, &texture_creator, &perf_text, 10, config.window_height as i32 - 40)?;
+        }
+
+        if let Some(v) = inspected.and_then(|id| world.vehicles.iter().find(|v| v.id == id)) {
+            let mut view = WorldView { canvas: &mut canvas, camera };
+            view.set_draw_color(Color::RGB(255, 0, 255));
+            view.draw_rect(outline_rect(v))?;
+            draw_inspector(&mut canvas, &font, &texture_creator, &config, v)?;
+        }
+
+        if show_wait_histogram {
+            draw_wait_histogram(&mut canvas, &config, world.metrics().wait_histogram())?;
+        }
+
+        // Config summary in the bottom-left corner so screenshots can be reproduced
+        render_text_overlay(&mut canvas, &font, &texture_creator, &world.config_summary(), 10, config.window_height as i32 - 20)?;
+
+        if screenshot_requested {
+            screenshot_requested = false;
+            // A failed write should not end the session, so it is only reported
+            match save_screenshot(&canvas, &config, world.ticks()) {
+                Ok(path) => println!("Saved screenshot to {}", path),
+                Err(e) => eprintln!("Screenshot failed: {}", e),
+            }
+        }
+
+        if let (Some(capture), Some(path)) = (&mut gif_capture, &gif_path) {
+            // A paused world keeps its tick, so that frame is captured only once
+            if capture.wants(world.ticks()) {
+                if !paused {
+                    capture.push(capture_frame(&canvas, &config, world.ticks())?);
+                }
+            } else if capture.is_finished(world.ticks()) {
+                match capture.write_to_file(path) {
+                    Ok(()) => println!("Wrote {} frames to {}", capture.frames().len(), path),
+                    Err(e) => eprintln!("Recording failed: {}", e),
+                }
+                gif_capture = None;
+            }
+        }
 
         canvas.present();
-        ::std::thread::sleep(Duration::from_millis(16));
+        ::std::thread::sleep(TICK_DURATION);
+    }
+
+    Ok(())
+}
+
+// Runs the simulation in the terminal with random spawns, for `max_ticks` updates or until interrupted
+fn run_headless(mut world: World, demand: Option<&DemandProfile>, max_ticks: Option<u64>) -> Result<(), String> {
+    let spawn_every = spawn_interval_ticks();
+    while max_ticks.is_none_or(|max| world.ticks() < max) {
+        match demand {
+            Some(profile) => world.spawn_from_demand(profile),
+            None if world.ticks().is_multiple_of(spawn_every) => {
+                world.spawn_random();
+            }
+            None => {}
+        }
+        world.update();
+        // Clear the terminal and redraw from the top-left corner
+        println!("\x1b[2J\x1b[H{}{}", ascii::render(&world), world.config_summary());
+        ::std::thread::sleep(TICK_DURATION);
     }
+    let metrics = world.metrics();
+    println!(
+        "Vehicles passed: {}, avg crossing: {:.0} ticks, max queue N/S/E/W: {:?}",
+        world.passed_count(),
+        metrics.avg_crossing_ticks(),
+        metrics.max_queue()
+    );
+    print_phase_history(&world.controller);
+    Ok(())
+}
 
+// Runs `ticks` updates as fast as possible with no rendering, spawning every `spawn_every` ticks
+// (or from the demand profile), and prints the final Stats as JSON for parameter sweeps
+fn run_batch(mut world: World, demand: Option<&DemandProfile>, ticks: u64, spawn_every: u64) -> Result<(), String> {
+    if spawn_every == 0 {
+        return Err("--spawn-every must be at least 1".to_string());
+    }
+    while world.ticks() < ticks {
+        match demand {
+            Some(profile) => world.spawn_from_demand(profile),
+            None if world.ticks().is_multiple_of(spawn_every) => {
+                world.spawn_random();
+            }
+            None => {}
+        }
+        world.update();
+    }
+    println!("{}", world.stats().to_json());
+    print_phase_history(&world.controller);
     Ok(())
 }
 
-fn handle_spawn_key(world: &mut World, last_spawn_time: &mut Instant, direction: Direction) {
+// One line per recorded phase change, on stderr so batch output stays plain JSON
+fn print_phase_history(controller: &TrafficLightController) {
+    for event in controller.phase_history() {
+        eprintln!("{:>8.3}s  {:<14}  waiting {}", event.started_at.as_secs_f32(), format!("{:?}", event.phase), event.waiting);
+    }
+}
+
+// Ticks between random spawns, matching the interactive SPAWN_TIMEOUT
+fn spawn_interval_ticks() -> u64 {
+    (SPAWN_TIMEOUT.as_millis() / TICK_DURATION.as_millis()) as u64
+}
+
+// On Linux a window needs an X11 or Wayland display; elsewhere assume one exists
+fn display_available() -> bool {
+    !cfg!(target_os = "linux")
+        || std::env::var_os("DISPLAY").is_some()
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("road_intersection: {}", message);
+    std::process::exit(1);
+}
+
+// Relative weights of cars, trucks and motorcycles among random spawns, e.g. `6,2,2`
+fn parse_vehicle_mix(list: &str) -> Result<[u32; 3], String> {
+    let weights = list
+        .split(',')
+        .map(|v| v.trim().parse::<u32>().map_err(|e| format!("invalid vehicle weight '{}': {}", v, e)))
+        .collect::<Result<Vec<u32>, String>>()?;
+    let [car, truck, motorcycle] = weights[..] else {
+        return Err(format!("--vehicle-mix expects 3 values (car, truck, motorcycle), found {}", weights.len()));
+    };
+    Ok([car, truck, motorcycle])
+}
+
+// Maximum greens in seconds for North, South, East and West, e.g. `4,4,2,2`
+fn parse_phase_durations(list: &str) -> Result<[Duration; 4], String> {
+    let durations = list
+        .split(',')
+        .map(|v| {
+            let seconds = v.trim().parse::<f32>().map_err(|e| format!("invalid phase duration '{}': {}", v, e))?;
+            Duration::try_from_secs_f32(seconds).map_err(|e| format!("invalid phase duration '{}': {}", v, e))
+        })
+        .collect::<Result<Vec<Duration>, String>>()?;
+    let [n, s, e, w] = durations[..] else {
+        return Err(format!("--phase-durations expects 4 values, found {}", durations.len()));
+    };
+    Ok([n, s, e, w])
+}
+
+// True when a value-less `--name` switch was given, e.g. `--headless`
+fn has_flag(name: &str) -> bool {
+    std::env::args().any(|a| a == name)
+}
+
+// Reads an optional `--name <value>` command line argument, e.g. `--seed 42`
+fn parse_arg<T: std::str::FromStr>(name: &str) -> Result<Option<T>, String>
+where
+    T::Err: std::fmt::Display,
+{
+    let args: Vec<String> = std::env::args().collect();
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            let value = args.get(i + 1).ok_or(format!("{} requires a value", name))?;
+            value.parse::<T>().map(Some).map_err(|e| format!("invalid value '{}' for {}: {}", value, name, e))
+        }
+        None => Ok(None),
+    }
+}
+
+// Reads back the rendered frame as RGBA bytes for the GIF recorder and screenshots.
+// RGBA32 is byte-ordered R, G, B, A on every platform, which is the layout both encoders take.
+fn capture_frame(canvas: &Canvas<Window>, config: &Config, tick: u64) -> Result<Frame, String> {
+    let rgba = canvas.read_pixels(None, PixelFormatEnum::RGBA32)?;
+    Ok(Frame {
+        width: config.window_width as u16,
+        height: config.window_height as u16,
+        tick,
+        rgba,
+    })
+}
+
+// Timestamp for output file names
+fn unix_seconds() -> Result<u64, String> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?.as_secs())
+}
+
+// Writes the current frame to screenshot-<unix seconds>-<tick>.png in the working directory
+fn save_screenshot(canvas: &Canvas<Window>, config: &Config, tick: u64) -> Result<String, String> {
+    let path = format!("screenshot-{}-{}.png", unix_seconds()?, tick);
+    capture_frame(canvas, config, tick)?.write_png(&path)?;
+    Ok(path)
+}
+
+// forced_turn, if set, applies to the next vehicle actually created and is cleared once used.
+// A spawn refused because the entry is still occupied keeps the forced turn and does not
+// restart the debounce, so holding the key places the car as soon as there is room.
+fn handle_spawn_key(world: &mut World, last_spawn_time: &mut Instant, direction: Direction, forced_turn: &mut Option<Turn>) {
     if last_spawn_time.elapsed() >= SPAWN_TIMEOUT {
-        world.spawn_vehicle(direction);
-        *last_spawn_time = Instant::now();
+        let spawned = match *forced_turn {
+            Some(turn) => world.spawn_vehicle_with_turn(direction, turn),
+            None => world.spawn_vehicle(direction),
+        };
+        if spawned.is_some() {
+            *forced_turn = None;
+            *last_spawn_time = Instant::now();
+        }
     }
 }
 
-fn draw_roads(canvas: &mut Canvas<Window>) -> Result<(), String> {
+fn draw_roads(canvas: &mut Canvas<Window>, config: &Config) -> Result<(), String> {
     canvas.set_draw_color(Color::RGB(100, 100, 100));
-    canvas.fill_rect(Rect::new(ROAD_X as i32, 0, ROAD_WIDTH, WINDOW_HEIGHT))?;
-    canvas.fill_rect(Rect::new(0, ROAD_Y as i32, WINDOW_WIDTH, ROAD_WIDTH))?;
+    canvas.fill_rect(Rect::new(config.road_x() as i32, 0, config.road_width, config.window_height))?;
+    canvas.fill_rect(Rect::new(0, config.road_y() as i32, config.window_width, config.road_width))?;
     Ok(())
 }
 
-fn draw_lanes(canvas: &mut Canvas<Window>) -> Result<(), String> {
+fn draw_lanes(canvas: &mut Canvas<Window>, config: &Config) -> Result<(), String> {
     canvas.set_draw_color(Color::RGB(120, 120, 120)); // Slightly lighter gray
+    let half_road = config.road_width / 2;
+    let quarter_road = (config.road_width / 4) as i32;
 
     // Northbound Lane (South to North)
-    let rect_northbound = Rect::new(NORTHBOUND_LANE_X - (ROAD_WIDTH / 4) as i32, 0, ROAD_WIDTH / 2, WINDOW_HEIGHT);
+    let rect_northbound = Rect::new(config.northbound_lane_x() - quarter_road, 0, half_road, config.window_height);
     canvas.fill_rect(rect_northbound)?;
     
     // Southbound Lane (North to South)
-    let rect_southbound = Rect::new(SOUTHBOUND_LANE_X - (ROAD_WIDTH / 4) as i32, 0, ROAD_WIDTH / 2, WINDOW_HEIGHT);
+    let rect_southbound = Rect::new(config.southbound_lane_x() - quarter_road, 0, half_road, config.window_height);
     canvas.fill_rect(rect_southbound)?;
     
     // Eastbound Lane (West to East)
-    let rect_eastbound = Rect::new(0, EASTBOUND_LANE_Y - (ROAD_WIDTH / 4) as i32, WINDOW_WIDTH, ROAD_WIDTH / 2);
+    let rect_eastbound = Rect::new(0, config.eastbound_lane_y() - quarter_road, config.window_width, half_road);
     canvas.fill_rect(rect_eastbound)?;
     
     // Westbound Lane (East to West)
-    let rect_westbound = Rect::new(0, WESTBOUND_LANE_Y - (ROAD_WIDTH / 4) as i32, WINDOW_WIDTH, ROAD_WIDTH / 2);
+    let rect_westbound = Rect::new(0, config.westbound_lane_y() - quarter_road, config.window_width, half_road);
     canvas.fill_rect(rect_westbound)?;
     Ok(())
 }
 
-fn draw_lane_dividers(canvas: &mut Canvas<Window>) -> Result<(), String> {
+fn draw_lane_dividers(canvas: &mut Canvas<Window>, config: &Config) -> Result<(), String> {
+    let (road_x, road_y) = (config.road_x() as i32, config.road_y() as i32);
+    let road_width = config.road_width as i32;
+    let (width, height) = (config.window_width as i32, config.window_height as i32);
     canvas.set_draw_color(Color::RGB(255, 255, 255));
-    for i in 0..15 {
-        // Horizontal road
-        if i * 60 + 30 < ROAD_X as i32 || i * 60 > (ROAD_X + ROAD_WIDTH) as i32 {
-            canvas.fill_rect(Rect::new(i * 60, (ROAD_Y + ROAD_WIDTH / 2 - 2) as i32, 30, 4))?;
+    // Horizontal road
+    for x in (0..width).step_by(60) {
+        if x + 30 < road_x || x > road_x + road_width {
+            canvas.fill_rect(Rect::new(x, road_y + road_width / 2 - 2, 30, 4))?;
+        }
+    }
+    // Vertical road
+    for y in (0..height).step_by(40) {
+        if y + 20 < road_y || y > road_y + road_width {
+            canvas.fill_rect(Rect::new(road_x + road_width / 2 - 2, y, 4, 20))?;
+        }
+    }
+
+    // Thinner dashes between the outer lane and the inner turn lane of each approach
+    for x in (0..width).step_by(30) {
+        if x + 15 < road_x {
+            canvas.fill_rect(Rect::new(x, config.eastbound_lane_y() - 1, 15, 2))?;
         }
-        // Vertical road
-        if i * 40 + 20 < ROAD_Y as i32 || i * 40 > (ROAD_Y + ROAD_WIDTH) as i32 {
-            canvas.fill_rect(Rect::new((ROAD_X + ROAD_WIDTH / 2 - 2) as i32, i * 40, 4, 20))?;
+        if x > road_x + road_width {
+            canvas.fill_rect(Rect::new(x, config.westbound_lane_y() - 1, 15, 2))?;
+        }
+    }
+    for y in (0..height).step_by(20) {
+        if y + 10 < road_y {
+            canvas.fill_rect(Rect::new(config.southbound_lane_x() - 1, y, 2, 10))?;
+        }
+        if y > road_y + road_width {
+            canvas.fill_rect(Rect::new(config.northbound_lane_x() - 1, y, 2, 10))?;
         }
     }
     Ok(())
 }
 
-fn draw_intersection_elements(canvas: &mut Canvas<Window>) -> Result<(), String> {
+fn draw_intersection_elements(canvas: &mut WorldView, config: &Config) -> Result<(), String> {
+    let (left, top) = (config.intersection_x_start() as i32, config.intersection_y_start() as i32);
+    let (right, bottom) = (config.intersection_x_end() as i32, config.intersection_y_end() as i32);
     canvas.set_draw_color(Color::RGB(200, 200, 200)); // Light gray for intersection outline
-    canvas.draw_rect(Rect::new(INTERSECTION_X_START as i32, INTERSECTION_Y_START as i32, ROAD_WIDTH, ROAD_WIDTH))?;
+    canvas.draw_rect(Rect::new(left, top, config.road_width, config.road_width))?;
 
+    // Stopping lines span the approach half of the road
+    let half_road = config.road_width / 2;
+    let quarter_road = (config.road_width / 4) as i32;
     canvas.set_draw_color(Color::RGB(255, 255, 255)); // White for stopping lines
     // North
-    canvas.fill_rect(Rect::new(SOUTHBOUND_LANE_X - 25, INTERSECTION_Y_START as i32 - 5, 50, 5))?;
+    canvas.fill_rect(Rect::new(config.southbound_lane_x() - quarter_road, top - 5, half_road, 5))?;
     // South
-    canvas.fill_rect(Rect::new(NORTHBOUND_LANE_X - 25, INTERSECTION_Y_END as i32, 50, 5))?;
+    canvas.fill_rect(Rect::new(config.northbound_lane_x() - quarter_road, bottom, half_road, 5))?;
     // East
-    canvas.fill_rect(Rect::new(INTERSECTION_X_END as i32, WESTBOUND_LANE_Y - 25, 5, 50))?;
+    canvas.fill_rect(Rect::new(right, config.westbound_lane_y() - quarter_road, 5, half_road))?;
     // West
-    canvas.fill_rect(Rect::new(INTERSECTION_X_START as i32 - 5, EASTBOUND_LANE_Y - 25, 5, 50))?;
+    canvas.fill_rect(Rect::new(left - 5, config.eastbound_lane_y() - quarter_road, 5, half_road))?;
     Ok(())
 }
 
-fn draw_traffic_lights(canvas: &mut Canvas<Window>, current_green_dir: &Direction) -> Result<(), String> {
-    let all_red = *current_green_dir == Direction::AllRed;
-    for dir in [
-        Direction::North,
-        Direction::South,
-        Direction::East,
-        Direction::West,
-    ]
-    {
-        let (x, y) = match dir {
-            Direction::North => (SOUTHBOUND_LANE_X - 50, INTERSECTION_Y_START as i32 - 25),
-            Direction::South => (NORTHBOUND_LANE_X + 30, INTERSECTION_Y_END as i32 + 5),
-            Direction::East => (INTERSECTION_X_END as i32 + 5, WESTBOUND_LANE_Y - 50),
-            Direction::West => (INTERSECTION_X_START as i32 - 25, EASTBOUND_LANE_Y + 30),
-            Direction::AllRed => (0, 0), // Placeholder, will be handled by all_red color below
-        };
-        if all_red {
-            canvas.set_draw_color(Color::RGB(255, 0, 0));
+// Pavement arrows showing the travel direction of each approach lane
+fn draw_lane_arrows(canvas: &mut WorldView, config: &Config) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGB(255, 255, 255));
+    for dir in Direction::APPROACHES {
+        let Some([tail, tip, left, right]) = lane_arrow(config, dir) else { continue };
+        canvas.draw_line(tail, tip)?;
+        canvas.draw_line(left, tip)?;
+        canvas.draw_line(right, tip)?;
+    }
+    Ok(())
+}
+
+const CROSSWALK_STRIPE: i32 = 5;
+
+// Zebra stripes across each arm just past its stop line, where pedestrians cross during a scramble
+fn draw_crosswalks(canvas: &mut WorldView, config: &Config) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGB(200, 200, 200));
+    let half = CROSSWALK_WIDTH / 2;
+    for dir in Direction::APPROACHES {
+        let Some((start, end)) = crosswalk(config, dir) else { continue };
+        if start.1 == end.1 {
+            for x in (start.0 + CROSSWALK_STRIPE..end.0).step_by(2 * CROSSWALK_STRIPE as usize) {
+                canvas.fill_rect(Rect::new(x, start.1 - half, CROSSWALK_STRIPE as u32, CROSSWALK_WIDTH as u32))?;
+            }
         } else {
-            if dir == *current_green_dir {
-                canvas.set_draw_color(Color::RGB(0, 255, 0));
-            } else {
-                canvas.set_draw_color(Color::RGB(255, 0, 0));
+            for y in (start.1 + CROSSWALK_STRIPE..end.1).step_by(2 * CROSSWALK_STRIPE as usize) {
+                canvas.fill_rect(Rect::new(start.0 - half, y, CROSSWALK_WIDTH as u32, CROSSWALK_STRIPE as u32))?;
             }
         }
-        canvas.fill_rect(Rect::new(x, y, 20, 20))?;
     }
     Ok(())
 }
 
-fn draw_vehicles(canvas: &mut Canvas<Window>, vehicles: &Vec<Vehicle>) -> Result<(), String> {
+fn draw_pedestrians(canvas: &mut WorldView, pedestrians: &[Pedestrian]) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGB(255, 140, 0)); // Orange, to stand out on the white stripes
+    let half = PEDESTRIAN_SIZE as i32 / 2;
+    for p in pedestrians {
+        canvas.fill_rect(Rect::new(p.x as i32 - half, p.y as i32 - half, PEDESTRIAN_SIZE, PEDESTRIAN_SIZE))?;
+    }
+    Ok(())
+}
+
+// Diagonal walk lines across the box while pedestrians may cross in every direction
+fn draw_scramble(canvas: &mut WorldView, config: &Config) -> Result<(), String> {
+    let (left, top) = (config.intersection_x_start() as i32, config.intersection_y_start() as i32);
+    let (right, bottom) = (config.intersection_x_end() as i32, config.intersection_y_end() as i32);
+    canvas.set_draw_color(Color::RGB(255, 255, 255));
+    canvas.draw_rect(Rect::new(left, top, config.road_width, config.road_width))?;
+    canvas.draw_line((left, top), (right, bottom))?;
+    canvas.draw_line((right, top), (left, bottom))?;
+    Ok(())
+}
+
+fn draw_traffic_lights(canvas: &mut WorldView, config: &Config, controller: &TrafficLightController) -> Result<(), String> {
+    for (dir, color) in controller.signal_states() {
+        let Some((x, y)) = signal_position(config, dir) else { continue };
+        canvas.set_draw_color(match color {
+            SignalColor::Red if controller.is_flashing() && !controller.flash_lit() => Color::RGB(60, 0, 0), // Unlit
+            SignalColor::Red => Color::RGB(255, 0, 0),
+            SignalColor::Yellow => Color::RGB(255, 191, 0), // Amber
+            SignalColor::Green => Color::RGB(0, 255, 0),
+        });
+        canvas.fill_rect(Rect::new(x, y, SIGNAL_SIZE, SIGNAL_SIZE))?;
+    }
+    Ok(())
+}
+
+// Seconds until the lights next change, beside every light
+fn draw_signal_countdowns(
+    canvas: &mut WorldView,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    config: &Config,
+    controller: &TrafficLightController,
+) -> Result<(), String> {
+    let Some(remaining) = controller.phase_countdown() else { return Ok(()) };
+    let text = format!("{:.1}", remaining.as_secs_f32());
+    for dir in Direction::APPROACHES {
+        let Some((x, y)) = countdown_position(config, dir) else { continue };
+        canvas.text(font, texture_creator, &text, x, y)?;
+    }
+    Ok(())
+}
+
+const TURN_SIGNAL_BLINK_FRAMES: u64 = 20; // Frames a turn signal stays on, then off
+
+const EMERGENCY_COLOR: Color = Color::RGB(255, 255, 255); // White, unlike any turn color
+
+fn turn_color(turn: Turn) -> Color {
+    match turn {
+        Turn::Left => Color::RGB(255, 255, 0), // Yellow
+        Turn::Right => Color::RGB(0, 255, 255), // Cyan
+        Turn::Straight => Color::RGB(255, 0, 255), // Magenta
+    }
+}
+
+// Debug view: the planned route of every vehicle as thin lines through its waypoints, with a dot
+// on each waypoint and a larger white one on the waypoint it is currently heading for
+fn draw_vehicle_paths(canvas: &mut WorldView, vehicles: &[Vehicle]) -> Result<(), String> {
+    let half = VEHICLE_SIZE as i32 / 2;
+    for v in vehicles {
+        canvas.set_draw_color(turn_color(v.turn));
+        for (from, to) in path_segments(&v.path) {
+            canvas.draw_line((from.0 + half, from.1 + half), (to.0 + half, to.1 + half))?;
+        }
+        for &(x, y) in &v.path {
+            canvas.fill_rect(Rect::new(x + half - 1, y + half - 1, 3, 3))?;
+        }
+        if let Some(&(x, y)) = v.path.get(v.path_index) {
+            canvas.set_draw_color(Color::RGB(255, 255, 255));
+            canvas.fill_rect(Rect::new(x + half - 3, y + half - 3, 7, 7))?;
+        }
+    }
+    Ok(())
+}
+
+fn draw_vehicles(canvas: &mut WorldView, config: &Config, vehicles: &Vec<Vehicle>, fade_ticks: u32, frame: u64) -> Result<(), String> {
     for v in vehicles {
-        let color = match v.turn {
-            Turn::Left => Color::RGB(255, 255, 0), // Yellow
-            Turn::Right => Color::RGB(0, 255, 255), // Cyan
-            Turn::Straight => Color::RGB(255, 0, 255), // Magenta
+        let color = if v.is_emergency { EMERGENCY_COLOR } else { turn_color(v.turn) };
+        let level = v.fade_level(fade_ticks);
+        let dim = |c: u8| (c as f32 * level) as u8;
+        canvas.set_draw_color(Color::RGB(dim(color.r), dim(color.g), dim(color.b)));
+        let body = vehicle_rect(v);
+        canvas.fill_rect(body)?;
+
+        // Arrowhead pointing along the heading so turns read clearly
+        let half = body.width().min(body.height()) as f32 / 2.0;
+        let center = (body.center().x() as f32, body.center().y() as f32);
+        let corner = |angle: f32, length: f32| {
+            let (sin, cos) = angle.sin_cos();
+            ((center.0 + cos * length).round() as i32, (center.1 + sin * length).round() as i32)
+        };
+        let tip = corner(v.heading(), half - 2.0);
+        let left = corner(v.heading() + 2.5, half - 3.0);
+        let right = corner(v.heading() - 2.5, half - 3.0);
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        canvas.draw_line(tip, left)?;
+        canvas.draw_line(left, right)?;
+        canvas.draw_line(right, tip)?;
+
+        // Turn signal on the front corner of the turning side, until the vehicle is through the box
+        let side = match v.turn {
+            Turn::Left => -1.0, // Screen y points down, so left of the heading is a negative angle
+            Turn::Right => 1.0,
+            Turn::Straight => continue,
         };
-        canvas.set_draw_color(color);
-        canvas.fill_rect(Rect::new(v.x, v.y, 20, 20))?;
+        let signalling = !v.passed && (v.path_index <= 1 || v.in_intersection(config));
+        if signalling && (frame / TURN_SIGNAL_BLINK_FRAMES).is_multiple_of(2) {
+            let (x, y) = corner(v.heading() + side * 0.8, half - 2.0);
+            canvas.set_draw_color(Color::RGB(0, 0, 0));
+            canvas.draw_rect(Rect::new(x - 3, y - 3, 6, 6))?;
+            canvas.set_draw_color(Color::RGB(255, 191, 0)); // Amber
+            canvas.fill_rect(Rect::new(x - 2, y - 2, 4, 4))?;
+        }
+    }
+    Ok(())
+}
+
+// Body of a vehicle in world coordinates, sized by its kind
+fn vehicle_rect(v: &Vehicle) -> Rect {
+    let (x, y, width, height) = v.footprint();
+    Rect::new(x, y, width, height)
+}
+
+// Highlight frame drawn 2 px outside a vehicle's body
+fn outline_rect(v: &Vehicle) -> Rect {
+    let body = vehicle_rect(v);
+    Rect::new(body.x() - 2, body.y() - 2, body.width() + 4, body.height() + 4)
+}
+
+// Outline the vehicles whose presence in the box forced the current AllRed
+fn draw_all_red_trigger(canvas: &mut WorldView, world: &World) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGB(255, 0, 0));
+    for v in world.vehicles.iter().filter(|v| world.all_red_trigger().contains(&v.id)) {
+        canvas.draw_rect(outline_rect(v))?;
     }
     Ok(())
 }
 
+// Outline vehicles that can neither stop nor clear the line before their green ends
+fn draw_dilemma_zone(canvas: &mut WorldView, world: &World) -> Result<(), String> {
+    let in_zone = world.vehicles_in_dilemma_zone();
+    canvas.set_draw_color(Color::RGB(255, 165, 0)); // Orange
+    for v in world.vehicles.iter().filter(|v| in_zone.contains(&v.id)) {
+        canvas.draw_rect(outline_rect(v))?;
+    }
+    Ok(())
+}
+
+// Wait-time distribution of completed vehicles as bars in the bottom-right corner
+fn draw_wait_histogram(canvas: &mut Canvas<Window>, config: &Config, bins: &[u32; WAIT_HISTOGRAM_BINS]) -> Result<(), String> {
+    const BAR_WIDTH: u32 = 16;
+    const MAX_HEIGHT: u32 = 60;
+    let right = config.window_width as i32 - 10;
+    let left = right - (BAR_WIDTH as i32 + 2) * WAIT_HISTOGRAM_BINS as i32;
+    let bottom = config.window_height as i32 - 10;
+    let max_count = bins.iter().copied().max().unwrap_or(0);
+
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    for (i, &count) in bins.iter().enumerate() {
+        let height = bar_height(count, max_count, MAX_HEIGHT);
+        if height == 0 {
+            continue;
+        }
+        let x = left + i as i32 * (BAR_WIDTH as i32 + 2);
+        canvas.fill_rect(Rect::new(x, bottom - height as i32, BAR_WIDTH, height))?;
+    }
+    canvas.draw_line((left, bottom), (right, bottom))?;
+    Ok(())
+}
+
+// Number of queued vehicles per approach, drawn next to its traffic light
+fn draw_queue_badges(
+    canvas: &mut WorldView,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    world: &World,
+) -> Result<(), String> {
+    for dir in Direction::APPROACHES {
+        let queued = world.queue_length(dir);
+        let Some((x, y)) = queue_badge_position(world.config(), dir) else { continue };
+        if queued == 0 {
+            continue;
+        }
+        canvas.set_draw_color(Color::RGB(255, 255, 255));
+        canvas.fill_rect(Rect::new(x, y, QUEUE_BADGE_SIZE, QUEUE_BADGE_SIZE))?;
+        canvas.text(font, texture_creator, &queued.to_string(), x + 3, y + 3)?;
+    }
+    Ok(())
+}
+
+// Debug view: each vehicle's id centered on it, to match cars with logged events
+fn draw_vehicle_ids(
+    canvas: &mut WorldView,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    vehicles: &[Vehicle],
+) -> Result<(), String> {
+    for v in vehicles.iter().filter(|v| !v.passed) {
+        let text = v.id.to_string();
+        let (width, height) = font.size_of(&text).map_err(|e| e.to_string())?;
+        let center = vehicle_rect(v).center();
+        canvas.text(font, texture_creator, &text, center.x() - width as i32 / 2, center.y() - height as i32 / 2)?;
+    }
+    Ok(())
+}
+
+// Debug view: each queued vehicle's position in its approach's arrival order
+fn draw_queue_order(
+    canvas: &mut WorldView,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    world: &World,
+) -> Result<(), String> {
+    for dir in Direction::APPROACHES {
+        for (position, id) in world.queue_order(dir).into_iter().enumerate() {
+            let Some(v) = world.vehicles.iter().find(|v| v.id == id) else { continue };
+            canvas.text(font, texture_creator, &(position + 1).to_string(), v.x + 5, v.y + 3)?;
+        }
+    }
+    Ok(())
+}
+
+// Vehicle under a clicked screen point, if any
+fn vehicle_at(world: &World, x: i32, y: i32) -> Option<u32> {
+    world
+        .vehicles
+        .iter()
+        .find(|v| !v.passed && vehicle_rect(v).contains_point((x, y)))
+        .map(|v| v.id)
+}
+
+// Side panel with the clicked vehicle's full state, one line per text row; the vehicle itself is
+// outlined by the caller in world space
+fn draw_inspector(
+    canvas: &mut Canvas<Window>,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    config: &Config,
+    vehicle: &Vehicle,
+) -> Result<(), String> {
+    for (i, line) in vehicle.debug_report(config).lines().enumerate() {
+        render_text_overlay(canvas, font, texture_creator, line, config.window_width as i32 - 220, 10 + i as i32 * 16)?;
+    }
+    Ok(())
+}
+
+// Canvas for world-space drawing: every shape goes through the camera first
+struct WorldView<'a> {
+    canvas: &'a mut Canvas<Window>,
+    camera: Camera,
+}
+
+impl WorldView<'_> {
+    fn set_draw_color(&mut self, color: Color) {
+        self.canvas.set_draw_color(color);
+    }
+
+    fn fill_rect(&mut self, rect: Rect) -> Result<(), String> {
+        let (x, y, width, height) = self.camera.to_screen_rect(rect.x(), rect.y(), rect.width(), rect.height());
+        self.canvas.fill_rect(Rect::new(x, y, width, height))
+    }
+
+    fn draw_rect(&mut self, rect: Rect) -> Result<(), String> {
+        let (x, y, width, height) = self.camera.to_screen_rect(rect.x(), rect.y(), rect.width(), rect.height());
+        self.canvas.draw_rect(Rect::new(x, y, width, height))
+    }
+
+    fn draw_line(&mut self, from: (i32, i32), to: (i32, i32)) -> Result<(), String> {
+        self.canvas.draw_line(self.camera.to_screen(from), self.camera.to_screen(to))
+    }
+
+    // Text anchored at a world point; the glyphs themselves keep their screen size
+    fn text(&mut self, font: &Font, texture_creator: &TextureCreator<WindowContext>, text: &str, x: i32, y: i32) -> Result<(), String> {
+        let (x, y) = self.camera.to_screen((x, y));
+        render_text_overlay(self.canvas, font, texture_creator, text, x, y)
+    }
+}
+
 fn render_text_overlay(
     canvas: &mut Canvas<Window>,
     font: &Font,
@@ -0,0 +1,159 @@
+use crate::vehicle::Vehicle;
+
+pub const WAIT_HISTOGRAM_BINS: usize = 8;
+pub const WAIT_BIN_TICKS: u32 = 60; // Width of one bin (about a second); the last bin also takes longer waits
+
+// Aggregates collected from vehicles as they leave the intersection
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metrics {
+    pub vehicles_passed: u32,
+    total_stops: u32,
+    total_wait_ticks: u64, // Ticks spent standing still, summed over completed vehicles
+    total_crossing_ticks: u64, // Spawn to departure, summed over completed vehicles
+    max_queue: [u32; 4],       // Longest queue seen per approach, indexed like Direction::APPROACHES
+    wait_histogram: [u32; WAIT_HISTOGRAM_BINS],
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            vehicles_passed: 0,
+            total_stops: 0,
+            total_wait_ticks: 0,
+            total_crossing_ticks: 0,
+            max_queue: [0; 4],
+            wait_histogram: [0; WAIT_HISTOGRAM_BINS],
+        }
+    }
+
+    pub fn record_departure(&mut self, v: &Vehicle, tick: u64) {
+        self.vehicles_passed += 1;
+        self.total_stops += v.stop_count;
+        self.total_wait_ticks += v.wait_ticks as u64;
+        self.total_crossing_ticks += tick - v.spawned_at;
+        self.wait_histogram[histogram_bin(v.wait_ticks)] += 1;
+    }
+
+    // Current queue lengths, indexed like Direction::APPROACHES
+    pub fn record_queues(&mut self, queues: [u32; 4]) {
+        for (max, queue) in self.max_queue.iter_mut().zip(queues) {
+            *max = (*max).max(queue);
+        }
+    }
+
+    pub fn max_queue(&self) -> [u32; 4] {
+        self.max_queue
+    }
+
+    // Average ticks from spawn to leaving the map
+    pub fn avg_crossing_ticks(&self) -> f32 {
+        if self.vehicles_passed == 0 {
+            return 0.0;
+        }
+        self.total_crossing_ticks as f32 / self.vehicles_passed as f32
+    }
+
+    // Average ticks a completed vehicle spent standing still
+    pub fn avg_wait_ticks(&self) -> f32 {
+        if self.vehicles_passed == 0 {
+            return 0.0;
+        }
+        self.total_wait_ticks as f32 / self.vehicles_passed as f32
+    }
+
+    // Completed vehicles per wait-time bin
+    pub fn wait_histogram(&self) -> &[u32; WAIT_HISTOGRAM_BINS] {
+        &self.wait_histogram
+    }
+
+    // Average number of times a completed vehicle had to come to a halt
+    pub fn avg_stops(&self) -> f32 {
+        if self.vehicles_passed == 0 {
+            return 0.0;
+        }
+        self.total_stops as f32 / self.vehicles_passed as f32
+    }
+}
+
+// Two moving vehicles that came within the near-miss distance of each other without touching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NearMiss {
+    pub tick: u64,
+    pub ids: (u32, u32), // Lower id first
+    pub positions: ((i32, i32), (i32, i32)), // Top-left corners, in the same order as ids
+}
+
+// End-of-run summary of a simulation, e.g. one point of a parameter sweep
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub ticks: u64,
+    pub vehicles_passed: u32,
+    pub throughput_per_minute: f32, // Vehicles passed per simulated minute
+    pub avg_wait_ticks: f32,
+    pub avg_crossing_ticks: f32,
+    pub avg_stops: f32,
+    pub collisions: u32,
+    pub near_misses: u32,
+}
+
+impl Stats {
+    // One flat JSON object, written by hand so batch runs do not need the serde feature
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"ticks\": {}, \"vehicles_passed\": {}, \"throughput_per_minute\": {:.2}, \"avg_wait_ticks\": {:.2}, \"avg_crossing_ticks\": {:.2}, \"avg_stops\": {:.2}, \"collisions\": {}, \"near_misses\": {}}}",
+            self.ticks,
+            self.vehicles_passed,
+            self.throughput_per_minute,
+            self.avg_wait_ticks,
+            self.avg_crossing_ticks,
+            self.avg_stops,
+            self.collisions,
+            self.near_misses
+        )
+    }
+}
+
+pub fn histogram_bin(wait_ticks: u32) -> usize {
+    ((wait_ticks / WAIT_BIN_TICKS) as usize).min(WAIT_HISTOGRAM_BINS - 1)
+}
+
+// Bar height in pixels, scaled so the fullest bin reaches max_height
+pub fn bar_height(count: u32, max_count: u32, max_height: u32) -> u32 {
+    if max_count == 0 {
+        return 0;
+    }
+    count * max_height / max_count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, Turn, World};
+
+    #[test]
+    fn wait_histogram_bins_completed_vehicles() {
+        let mut world = World::with_seed(0);
+        world.add_vehicle(Direction::North, Turn::Straight, (0.0, 0.0), 0);
+        let mut v = world.vehicles.pop().unwrap();
+        let mut metrics = Metrics::new();
+        for wait_ticks in [0, 59, 60, 130, 179, 420, 10_000] {
+            v.wait_ticks = wait_ticks;
+            metrics.record_departure(&v, 0);
+        }
+        assert_eq!(metrics.wait_histogram(), &[2, 1, 2, 0, 0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn bar_height_scales_to_the_fullest_bin() {
+        assert_eq!(bar_height(4, 4, 60), 60);
+        assert_eq!(bar_height(1, 4, 60), 15);
+        assert_eq!(bar_height(0, 0, 60), 0);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::events::SimEvent;
+use crate::vehicle::VehicleKind;
+use crate::{Direction, Turn, World};
+
+pub const DEFAULT_TRAVEL_TICKS: u32 = 60; // Ticks to drive the road segment between two intersections
+const IDS_PER_INTERSECTION: u32 = 1 << 24; // Each intersection numbers its own vehicles from its own range
+
+// A vehicle on the road segment between two neighbouring intersections, arriving as the same
+// vehicle with the same turn it had at the last one
+struct Transit {
+    target: usize,
+    approach: Direction,
+    remaining_ticks: u32,
+    id: u32,
+    turn: Turn,
+    kind: VehicleKind,
+}
+
+// Intersections placed west to east along a shared east-west road. A vehicle that leaves
+// one intersection through its east or west edge arrives at the neighbour on that side.
+pub struct Network {
+    pub intersections: Vec<World>,
+    pub travel_ticks: u32,
+    in_transit: Vec<Transit>,
+}
+
+impl Network {
+    pub fn new(count: usize, seed: u64) -> Self {
+        Self {
+            intersections: (0..count)
+                .map(|i| {
+                    let mut world = World::with_seed(seed.wrapping_add(i as u64));
+                    // Vehicles keep their id along the corridor, so no two intersections hand out the same one
+                    world.next_id = i as u32 * IDS_PER_INTERSECTION;
+                    world
+                })
+                .collect(),
+            travel_ticks: DEFAULT_TRAVEL_TICKS,
+            in_transit: Vec::new(),
+        }
+    }
+
+    // Number of vehicles currently driving between intersections
+    pub fn in_transit(&self) -> usize {
+        self.in_transit.len()
+    }
+
+    pub fn update(&mut self) {
+        // Deliver vehicles that finished the segment; a full approach keeps them waiting on the road
+        for transit in &mut self.in_transit {
+            transit.remaining_ticks = transit.remaining_ticks.saturating_sub(1);
+        }
+        let intersections = &mut self.intersections;
+        self.in_transit.retain(|t| {
+            t.remaining_ticks > 0 || intersections[t.target].admit_vehicle(t.id, t.approach, t.turn, t.kind).is_none()
+        });
+
+        for i in 0..self.intersections.len() {
+            // Departed vehicles are dropped during the update, so note their turn and kind beforehand
+            let travellers: Vec<(u32, Turn, VehicleKind)> =
+                self.intersections[i].vehicles.iter().map(|v| (v.id, v.turn, v.kind)).collect();
+            self.intersections[i].update();
+
+            for event in self.intersections[i].events() {
+                let SimEvent::Departed { id, exit } = event else { continue };
+                let Some(&(id, turn, kind)) = travellers.iter().find(|(traveller, ..)| traveller == id) else { continue };
+                let next = match exit {
+                    Direction::East if i + 1 < self.intersections.len() => (i + 1, Direction::West),
+                    Direction::West if i > 0 => (i - 1, Direction::East),
+                    _ => continue, // Left the corridor
+                };
+                self.in_transit.push(Transit {
+                    target: next.0,
+                    approach: next.1,
+                    remaining_ticks: self.travel_ticks,
+                    id,
+                    turn,
+                    kind,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MAX_SPEED;
+
+    #[test]
+    fn westbound_vehicle_reaches_the_next_intersection_after_the_travel_time() {
+        let mut network = Network::new(2, 1);
+        network.intersections[1].spawn_vehicle_with_turn(Direction::East, Turn::Straight).unwrap();
+
+        let mut departed_at = None;
+        let mut tick = 0;
+        while network.intersections[0].vehicles.is_empty() {
+            network.update();
+            tick += 1;
+            assert!(tick < 2000, "never arrived");
+            let departed = network.intersections[1]
+                .events()
+                .iter()
+                .any(|e| matches!(e, SimEvent::Departed { exit: Direction::West, .. }));
+            if departed {
+                departed_at = Some(tick);
+                assert_eq!(network.in_transit(), 1);
+            }
+        }
+        assert_eq!(tick - departed_at.unwrap(), network.travel_ticks);
+        assert_eq!(network.intersections[0].vehicles[0].dir, Direction::East);
+    }
+
+    #[test]
+    fn vehicle_keeps_its_identity_between_intersections() {
+        let mut network = Network::new(2, 1);
+        let id = network.intersections[0].spawn_vehicle_with_turn(Direction::West, Turn::Straight).unwrap();
+        network.intersections[0].vehicles[0].set_kind(VehicleKind::Truck, MAX_SPEED);
+        let local = network.intersections[1].spawn_vehicle(Direction::North).unwrap();
+        assert_ne!(id, local);
+
+        let mut tick = 0;
+        while !network.intersections[1].vehicles.iter().any(|v| v.dir == Direction::West) {
+            network.update();
+            tick += 1;
+            assert!(tick < 3000, "never arrived");
+        }
+        let arrived = network.intersections[1].vehicles.iter().find(|v| v.dir == Direction::West).unwrap();
+        assert_eq!((arrived.id, arrived.kind, arrived.turn), (id, VehicleKind::Truck, Turn::Straight));
+    }
+}
@@ -0,0 +1,61 @@
+use crate::Direction;
+use crate::config::Config;
+
+pub const PEDESTRIAN_SPEED: f32 = 1.5; // Walking speed in pixels per tick
+pub const PEDESTRIAN_SIZE: u32 = 4;
+pub const CROSSWALK_WIDTH: i32 = 10; // Depth of the crosswalk band along the edge of the box
+
+// Centre line of the crosswalk across the arm of dir, running along the edge of the box just
+// past its stop line, from curb to curb
+pub fn crosswalk(config: &Config, dir: Direction) -> Option<((i32, i32), (i32, i32))> {
+    let (left, top) = (config.intersection_x_start() as i32, config.intersection_y_start() as i32);
+    let (right, bottom) = (config.intersection_x_end() as i32, config.intersection_y_end() as i32);
+    let inset = CROSSWALK_WIDTH / 2;
+    match dir {
+        Direction::North => Some(((left, top + inset), (right, top + inset))),
+        Direction::South => Some(((left, bottom - inset), (right, bottom - inset))),
+        Direction::East => Some(((right - inset, top), (right - inset, bottom))),
+        Direction::West => Some(((left + inset, top), (left + inset, bottom))),
+        Direction::AllRed => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pedestrian {
+    pub id: u32,
+    pub crossing: Direction, // Approach whose arm this pedestrian crosses
+    pub x: f32, // Centre of the dot
+    pub y: f32,
+    pub target: (f32, f32), // Far curb
+    pub walking: bool, // False while waiting at the curb for the pedestrian phase
+}
+
+impl Pedestrian {
+    // Waiting at one end of the crosswalk of dir; from_start picks the left or top curb
+    pub fn new(config: &Config, id: u32, crossing: Direction, from_start: bool) -> Option<Self> {
+        let (start, end) = crosswalk(config, crossing)?;
+        let (from, to) = if from_start { (start, end) } else { (end, start) };
+        Some(Self {
+            id,
+            crossing,
+            x: from.0 as f32,
+            y: from.1 as f32,
+            target: (to.0 as f32, to.1 as f32),
+            walking: false,
+        })
+    }
+
+    // One tick of walking towards the far curb; returns true once it has been reached
+    pub fn advance(&mut self) -> bool {
+        let (dx, dy) = (self.target.0 - self.x, self.target.1 - self.y);
+        let remaining = (dx * dx + dy * dy).sqrt();
+        if remaining <= PEDESTRIAN_SPEED {
+            (self.x, self.y) = self.target;
+            return true;
+        }
+        self.x += dx / remaining * PEDESTRIAN_SPEED;
+        self.y += dy / remaining * PEDESTRIAN_SPEED;
+        false
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const AVERAGE_WINDOW: usize = 60; // Number of ticks in the rolling average (about one second)
+
+// Timing of World::update, only collected while enabled on the world
+pub struct PerfStats {
+    samples: VecDeque<Duration>,
+    total: Duration,
+}
+
+impl Default for PerfStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PerfStats {
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(AVERAGE_WINDOW),
+            total: Duration::ZERO,
+        }
+    }
+
+    pub fn record(&mut self, elapsed: Duration) {
+        if self.samples.len() == AVERAGE_WINDOW {
+            let oldest = self.samples.pop_front().unwrap_or_default();
+            self.total -= oldest;
+        }
+        self.samples.push_back(elapsed);
+        self.total += elapsed;
+    }
+
+    pub fn last_micros(&self) -> f64 {
+        self.samples.back().map_or(0.0, |d| d.as_secs_f64() * 1_000_000.0)
+    }
+
+    pub fn average_micros(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.total.as_secs_f64() * 1_000_000.0 / self.samples.len() as f64
+    }
+}
@@ -0,0 +1,93 @@
+use crate::demand::parse_direction;
+use crate::vehicle::VehicleKind;
+use crate::{Direction, Turn};
+
+// One vehicle of a scenario, placed partway along its route
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioVehicle {
+    pub dir: Direction,
+    pub turn: Turn,
+    pub kind: VehicleKind,
+    pub pos: (f32, f32),
+    pub path_index: usize,
+}
+
+// A set of vehicles to start a run from, stored as CSV lines of `direction,turn,x,y,path_index`
+// with an optional sixth `kind` field, car when left out. Blank lines and lines starting with '#'
+// are ignored, so files can be edited by hand.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scenario {
+    pub vehicles: Vec<ScenarioVehicle>,
+}
+
+impl Scenario {
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&text)
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_text()).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut vehicles = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let vehicle = parse_vehicle(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+            vehicles.push(vehicle);
+        }
+        Ok(Self { vehicles })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# direction,turn,x,y,path_index,kind\n");
+        for v in &self.vehicles {
+            text.push_str(&format!(
+                "{:?},{:?},{},{},{},{:?}\n",
+                v.dir, v.turn, v.pos.0, v.pos.1, v.path_index, v.kind
+            ));
+        }
+        text
+    }
+}
+
+fn parse_vehicle(line: &str) -> Result<ScenarioVehicle, String> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let (dir, turn, x, y, path_index, kind) = match fields[..] {
+        [dir, turn, x, y, path_index] => (dir, turn, x, y, path_index, None),
+        [dir, turn, x, y, path_index, kind] => (dir, turn, x, y, path_index, Some(kind)),
+        _ => return Err(format!("expected 5 or 6 fields, found {}", fields.len())),
+    };
+    let x = x.parse::<f32>().map_err(|e| format!("x '{}': {}", x, e))?;
+    let y = y.parse::<f32>().map_err(|e| format!("y '{}': {}", y, e))?;
+    let path_index = path_index.parse::<usize>().map_err(|e| format!("path index '{}': {}", path_index, e))?;
+    Ok(ScenarioVehicle {
+        dir: parse_direction(dir)?,
+        turn: parse_turn(turn)?,
+        kind: kind.map_or(Ok(VehicleKind::Car), parse_kind)?,
+        pos: (x, y),
+        path_index,
+    })
+}
+
+fn parse_turn(s: &str) -> Result<Turn, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "l" | "left" => Ok(Turn::Left),
+        "r" | "right" => Ok(Turn::Right),
+        "s" | "straight" => Ok(Turn::Straight),
+        _ => Err(format!("unknown turn '{}'", s)),
+    }
+}
+
+fn parse_kind(s: &str) -> Result<VehicleKind, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "c" | "car" => Ok(VehicleKind::Car),
+        "t" | "truck" => Ok(VehicleKind::Truck),
+        "m" | "motorcycle" => Ok(VehicleKind::Motorcycle),
+        _ => Err(format!("unknown vehicle kind '{}'", s)),
+    }
+}
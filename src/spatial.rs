@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+// Uniform grid over vehicle positions, so proximity checks only visit nearby cells
+// instead of every other vehicle
+pub struct SpatialGrid {
+    cell_size: i32,
+    cells: HashMap<(i32, i32), Vec<usize>>, // Item indices by cell coordinates
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: i32) -> Self {
+        Self {
+            cell_size: cell_size.max(1),
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, x: i32, y: i32) -> (i32, i32) {
+        (x.div_euclid(self.cell_size), y.div_euclid(self.cell_size))
+    }
+
+    pub fn insert(&mut self, index: usize, x: i32, y: i32) {
+        let cell = self.cell(x, y);
+        self.cells.entry(cell).or_default().push(index);
+    }
+
+    // Indices of every item whose position lies within `reach` of (x, y) on both axes,
+    // possibly along with a few slightly farther ones from the same cells
+    pub fn nearby(&self, x: i32, y: i32, reach: i32) -> impl Iterator<Item = usize> + '_ {
+        let (min_x, min_y) = self.cell(x - reach, y - reach);
+        let (max_x, max_y) = self.cell(x + reach, y + reach);
+        (min_x..=max_x)
+            .flat_map(move |cx| (min_y..=max_y).map(move |cy| (cx, cy)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+}
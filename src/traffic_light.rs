@@ -1,31 +1,524 @@
-use std::time::{Duration, Instant};
+use std::collections::VecDeque;
+use std::time::Duration;
 use crate::Direction;
 
-const MAX_PHASE_DURATION: Duration = Duration::from_secs(3); // Maximum duration for each green light phase
+pub const MAX_PHASE_DURATION: Duration = Duration::from_secs(3); // Maximum duration for each green light phase
 const NO_CARS_DELAY: Duration = Duration::from_millis(200); // Time to wait for cars before switching the light
+const GAP_EXTENSION: Duration = Duration::from_millis(500); // Extra green granted for a vehicle about to reach the line
+const MAX_EXTENSION: Duration = Duration::from_millis(1500); // Cap on the extra green added to a single phase
+pub const MIN_GREEN: Duration = Duration::from_secs(1); // Shortest green, even with nobody waiting, so sparse traffic does not flicker the lights
+pub const YELLOW_DURATION: Duration = Duration::from_secs(1); // Amber shown between a green and the following AllRed
+pub const STARTUP_DELAY: Duration = Duration::from_secs(2); // AllRed hold before a fixed cycle starts from AllRed
+pub const MAX_WEIGHTED_GREEN: Duration = Duration::from_secs(6); // Default cap on a queue-weighted green
+pub const FLASH_RESUME_QUEUE: u32 = 4; // Vehicles queued in total that end flashing red and restore timed operation
+const FLASH_PERIOD: Duration = Duration::from_millis(500); // Each on or off half of the flashing red blink
+pub const SCRAMBLE_DURATION: Duration = Duration::from_secs(2); // Default length of a pedestrian scramble
+pub const PHASE_HISTORY_CAP: usize = 256; // Default number of phase changes kept once the history is enabled
 
-// Traffic light controller: cycles through 4 directions in order
+// How the controller decides when a green phase ends, and so which approach goes next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SignalStrategy {
+    FixedCycle, // Every green runs for the full phase duration, North -> South -> East -> West
+    Actuated,   // A green ends early once its approach has no waiting vehicles; the next goes to a waiting approach by TieBreak
+    LongestWaiting, // Ends early like Actuated, then serves the approach whose vehicle has waited longest, so arrivals during AllRed keep their turn
+}
+
+// Light shown to a single approach
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalColor {
+    Red,
+    Yellow,
+    Green,
+}
+
+// Full controller state: which approach, if any, has a green or amber light
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Phase {
+    Green(Direction),
+    Yellow(Direction),
+    AllRed,
+}
+
+// Which approach an Actuated controller serves when several have waiting vehicles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TieBreak {
+    RoundRobin,   // The next one in cycle order after the last green
+    LongestQueue, // The one with the most waiting vehicles, cycle order among equals
+    FixedOrder,   // Always the first in North, South, East, West order
+}
+
+// A phase change recorded by the phase history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhaseEvent {
+    pub phase: Phase,        // Phase entered
+    pub started_at: Duration, // Controller clock at the update that saw the change
+    pub waiting: u32,        // Vehicles waiting at all stop lines at that update
+}
+
+// What the controller observes about traffic on each update
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SignalInputs {
+    pub waiting_vehicles: [u32; 4], // Vehicles at each stop line, indexed like Direction::APPROACHES
+    pub cars_in_intersection: bool, // Any vehicle inside the box
+    pub vehicles_on_stop_line: bool, // Any vehicle straddling a stop line
+    pub vehicle_approaching: bool,  // A vehicle of the green approach is about to reach its stop line
+    pub longest_wait: [u32; 4],     // Ticks the longest-waiting vehicle of each approach has stood still
+    pub queue_lengths: [u32; 4],    // Vehicles stopped in each approach's queue
+}
+
+// Traffic light controller: serves one approach at a time, or opposing pairs when paired, with
+// green lengths and the next green set by the SignalStrategy and amber then AllRed between greens. It can start
+// in AllRed, insert pedestrian scrambles, drop to flashing red when idle, and be overridden by
+// request_green or emergency preemption.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TrafficLightController {
     pub current: Direction,
-    last_switch: Instant,
-    max_phase_duration: Duration,
-    last_car_cleared_time: Option<Instant>,
+    strategy: SignalStrategy,
+    tie_break: TieBreak,
+    adaptive_timing: bool,
+    clock: Duration, // Logical time, advanced only by the dt passed to update
+    last_switch: Duration,
+    phase_durations: [Duration; 4], // Maximum green per approach, indexed like Direction::APPROACHES
+    extension: Duration, // Extra green granted to the current phase so far
+    min_green: Duration,
+    yellow_duration: Duration,
+    yellow: bool, // current is showing amber on its way to AllRed
+    last_car_cleared_time: Option<Duration>,
     last_green_direction: Direction,
+    requested: Option<Direction>,
+    preempted: Option<Direction>, // Approach held green for an emergency vehicle
+    scramble_every: u32, // Full cycles between pedestrian scrambles, 0 disables them
+    scramble_duration: Duration,
+    greens_since_scramble: u32,
+    scrambling: bool,
+    awaiting_start: bool, // Started in AllRed and no green has been shown yet
+    start_phase: Direction, // Phase restored by reset
+    paired: bool, // Opposing approaches share each green: North with South, East with West
+    green_per_vehicle: Duration, // Green granted per queued vehicle, zero keeps the fixed phase durations
+    max_weighted_green: Duration,
+    queue_lengths: [u32; 4], // From the latest update, used to size the next green
+    weighted_green: Option<Duration>, // Length of the current green when queue weighting is on
+    flash_after: Duration, // Idle time before switching to flashing red, zero disables it
+    idle_since: Option<Duration>,
+    flashing: bool, // Night mode: an all-way stop releasing one vehicle at a time
+    history: VecDeque<PhaseEvent>, // Oldest first
+    history_cap: usize, // Phase changes kept, zero disables the history
+    history_phase: Phase, // Phase as of the latest update, to spot changes made outside update too
 }
 
 impl TrafficLightController {
     pub fn new() -> Self {
+        Self::with_strategy(SignalStrategy::Actuated)
+    }
+
+    pub fn with_strategy(strategy: SignalStrategy) -> Self {
         Self {
             current: Direction::North,
-            last_switch: Instant::now(),
-            max_phase_duration: MAX_PHASE_DURATION, // Initialize maximum phase duration
+            strategy,
+            tie_break: TieBreak::RoundRobin,
+            adaptive_timing: true,
+            clock: Duration::ZERO,
+            last_switch: Duration::ZERO,
+            phase_durations: [MAX_PHASE_DURATION; 4], // Initialize maximum phase durations
+            extension: Duration::ZERO,
+            min_green: MIN_GREEN,
+            yellow_duration: YELLOW_DURATION,
+            yellow: false,
             last_car_cleared_time: None,
             last_green_direction: Direction::West, // Initialize to West so North is the first green
+            requested: None,
+            preempted: None,
+            scramble_every: 0,
+            scramble_duration: SCRAMBLE_DURATION,
+            greens_since_scramble: 0,
+            scrambling: false,
+            awaiting_start: false,
+            start_phase: Direction::North,
+            paired: false,
+            green_per_vehicle: Duration::ZERO,
+            max_weighted_green: MAX_WEIGHTED_GREEN,
+            queue_lengths: [0; 4],
+            weighted_green: None,
+            flash_after: Duration::ZERO,
+            idle_since: None,
+            flashing: false,
+            history: VecDeque::new(),
+            history_cap: 0,
+            history_phase: Phase::Green(Direction::North),
+        }
+    }
+
+    // Phase shown before the first update. Starting in AllRed holds every approach until the
+    // first vehicle waits at a line, or for STARTUP_DELAY under FixedCycle which ignores demand.
+    pub fn set_start_phase(&mut self, phase: Direction) {
+        self.start_phase = phase;
+        self.current = phase;
+        self.last_switch = self.clock;
+        self.awaiting_start = phase == Direction::AllRed;
+        if phase != Direction::AllRed {
+            self.last_green_direction = phase;
+        }
+    }
+
+    // Back to the state before the first update, keeping the strategy, timings and other settings
+    pub fn reset(&mut self) {
+        self.clock = Duration::ZERO;
+        self.extension = Duration::ZERO;
+        self.yellow = false;
+        self.last_car_cleared_time = None;
+        self.last_green_direction = Direction::West;
+        self.requested = None;
+        self.preempted = None;
+        self.greens_since_scramble = 0;
+        self.scrambling = false;
+        self.queue_lengths = [0; 4];
+        self.weighted_green = None;
+        self.idle_since = None;
+        self.flashing = false;
+        self.set_start_phase(self.start_phase);
+        self.history.clear();
+        self.history_phase = self.phase();
+    }
+
+    pub fn strategy(&self) -> SignalStrategy {
+        self.strategy
+    }
+
+    pub fn tie_break(&self) -> TieBreak {
+        self.tie_break
+    }
+
+    pub fn set_tie_break(&mut self, tie_break: TieBreak) {
+        self.tie_break = tie_break;
+    }
+
+    pub fn paired(&self) -> bool {
+        self.paired
+    }
+
+    // Alternate North+South and East+West greens instead of serving one approach at a time.
+    // Only straights and turns towards the curb flow freely on a shared green; World holds
+    // turns across the oncoming lanes for a gap in the opposing approach.
+    pub fn set_paired(&mut self, paired: bool) {
+        self.paired = paired;
+    }
+
+    // Maximum green for an approach; zero for AllRed
+    pub fn phase_duration(&self, dir: Direction) -> Duration {
+        dir.approach_index().map_or(Duration::ZERO, |i| self.phase_durations[i])
+    }
+
+    pub fn phase_durations(&self) -> [Duration; 4] {
+        self.phase_durations
+    }
+
+    // Separate maximum greens, e.g. longer for a main road than for a side street
+    pub fn set_phase_durations(&mut self, durations: [Duration; 4]) {
+        self.phase_durations = durations;
+    }
+
+    // The same maximum green for all four approaches
+    pub fn set_max_phase_duration(&mut self, duration: Duration) {
+        self.phase_durations = [duration; 4];
+    }
+
+    // Time spent in the current phase
+    pub fn phase_elapsed(&self) -> Duration {
+        self.clock - self.last_switch
+    }
+
+    // Time left before the current approach turns red: the rest of the green (including any
+    // gap extension granted so far) plus the amber, or the rest of the amber; zero during AllRed
+    pub fn remaining_time(&self) -> Duration {
+        if self.current == Direction::AllRed {
+            return Duration::ZERO;
+        }
+        if self.yellow {
+            return self.yellow_duration.saturating_sub(self.phase_elapsed());
+        }
+        (self.green_duration(self.current) + self.extension + self.yellow_duration).saturating_sub(self.phase_elapsed())
+    }
+
+    // Countdown shown beside the lights: remaining_time while an approach is served, otherwise the
+    // rest of a scramble or of the FixedCycle startup hold. None when the next change only waits
+    // on traffic, i.e. a clearance AllRed waiting for the box or flashing red.
+    pub fn phase_countdown(&self) -> Option<Duration> {
+        if self.flashing {
+            return None;
+        }
+        if self.current != Direction::AllRed {
+            return Some(self.remaining_time());
+        }
+        if self.scrambling {
+            return Some(self.scramble_duration.saturating_sub(self.phase_elapsed()));
+        }
+        if self.awaiting_start && self.strategy == SignalStrategy::FixedCycle {
+            return Some(STARTUP_DELAY.saturating_sub(self.phase_elapsed()));
+        }
+        None
+    }
+
+    pub fn phase(&self) -> Phase {
+        match self.current {
+            Direction::AllRed => Phase::AllRed,
+            dir if self.yellow => Phase::Yellow(dir),
+            dir => Phase::Green(dir),
+        }
+    }
+
+    // Whether dir shows the current phase's light: the current approach, plus its opposite when
+    // paired. Flashing red releases a single approach even when paired.
+    pub fn serves(&self, dir: Direction) -> bool {
+        self.current != Direction::AllRed
+            && (dir == self.current || (self.paired && !self.flashing && dir == self.current.opposite()))
+    }
+
+    // Only a green lets vehicles cross the stop line; amber already holds them
+    pub fn is_green(&self, dir: Direction) -> bool {
+        !self.yellow && self.serves(dir)
+    }
+
+    pub fn min_green(&self) -> Duration {
+        self.min_green
+    }
+
+    // Rule 1 never ends a green earlier than this; the maximum phase duration still applies
+    pub fn set_min_green(&mut self, duration: Duration) {
+        self.min_green = duration;
+    }
+
+    pub fn yellow_duration(&self) -> Duration {
+        self.yellow_duration
+    }
+
+    // Zero switches straight from green to the next phase, as without an amber light
+    pub fn set_yellow_duration(&mut self, duration: Duration) {
+        self.yellow_duration = duration;
+    }
+
+    // Light shown to every approach, in Direction::APPROACHES order; all red during AllRed
+    pub fn signal_states(&self) -> impl Iterator<Item = (Direction, SignalColor)> + '_ {
+        Direction::APPROACHES.into_iter().map(|dir| {
+            let color = match self.phase() {
+                _ if self.flashing => SignalColor::Red, // Every approach stops, even the one being released
+                Phase::Green(_) if self.serves(dir) => SignalColor::Green,
+                Phase::Yellow(_) if self.serves(dir) => SignalColor::Yellow,
+                _ => SignalColor::Red,
+            };
+            (dir, color)
+        })
+    }
+
+    pub fn adaptive_timing(&self) -> bool {
+        self.adaptive_timing
+    }
+
+    // With adaptive timing off every green lasts exactly its phase duration, even under a strategy
+    // that would end it early or extend it; which approach goes next is still up to the strategy
+    pub fn set_adaptive_timing(&mut self, enabled: bool) {
+        self.adaptive_timing = enabled;
+    }
+
+    // Greens end early or get extended only under a demand-driven strategy with adaptation on
+    fn actuated_timing(&self) -> bool {
+        self.adaptive_timing && self.strategy != SignalStrategy::FixedCycle
+    }
+
+    // One full cycle as scheduled without traffic: every green at its maximum, each followed by its
+    // amber and the AllRed clearance. AllRed is listed as zero, since it only lasts until the box is
+    // clear; adaptive timing and queue weighting change the greens, and scrambles are not included.
+    // When paired each green stands for the approach and its opposite.
+    pub fn plan(&self) -> Vec<(Phase, Duration)> {
+        let mut plan = Vec::new();
+        let mut dir = Direction::North;
+        for _ in 0..self.cycle_len() {
+            plan.push((Phase::Green(dir), self.scheduled_green(dir)));
+            if !self.yellow_duration.is_zero() {
+                plan.push((Phase::Yellow(dir), self.yellow_duration));
+                plan.push((Phase::AllRed, Duration::ZERO));
+            }
+            dir = self.cycle_successor(dir);
+        }
+        plan
+    }
+
+    // Keep the last `cap` phase changes for phase_history, dropping the oldest beyond that.
+    // Zero turns the history off and discards it.
+    pub fn set_phase_history(&mut self, cap: usize) {
+        self.history_cap = cap;
+        self.history_phase = self.phase();
+        if cap == 0 {
+            self.history.clear();
+        } else if self.history.len() > cap {
+            self.history.drain(..self.history.len() - cap);
+        }
+    }
+
+    // Phase changes seen by update, oldest first; empty unless enabled with set_phase_history
+    pub fn phase_history(&self) -> &VecDeque<PhaseEvent> {
+        &self.history
+    }
+
+    fn record_phase(&mut self, waiting_vehicles: [u32; 4]) {
+        let phase = self.phase();
+        if phase == self.history_phase {
+            return;
+        }
+        self.history_phase = phase;
+        if self.history_cap == 0 {
+            return;
+        }
+        if self.history.len() >= self.history_cap {
+            self.history.pop_front();
+        }
+        self.history.push_back(PhaseEvent { phase, started_at: self.clock, waiting: waiting_vehicles.iter().sum() });
+    }
+
+    // Greens in one full cycle
+    fn cycle_len(&self) -> usize {
+        if self.paired { 2 } else { Direction::APPROACHES.len() }
+    }
+
+    // After `idle` with no vehicle waiting, approaching or in the box, the lights switch to flashing
+    // red and the intersection runs as an all-way stop until FLASH_RESUME_QUEUE vehicles queue up.
+    // Zero turns the night mode off.
+    pub fn set_flashing_red(&mut self, idle: Duration) {
+        self.flash_after = idle;
+        self.idle_since = None;
+        if idle.is_zero() {
+            self.flashing = false;
+        }
+    }
+
+    pub fn flash_after(&self) -> Duration {
+        self.flash_after
+    }
+
+    pub fn is_flashing(&self) -> bool {
+        self.flashing
+    }
+
+    // Whether a flashing red is lit right now; it blinks with FLASH_PERIOD
+    pub fn flash_lit(&self) -> bool {
+        (self.clock.as_millis() / FLASH_PERIOD.as_millis()).is_multiple_of(2)
+    }
+
+    // Enters and leaves flashing red, and while flashing lets one stopped vehicle at a time into
+    // the empty box, taking the approaches round-robin. Returns true while flashing, so the
+    // timed logic is skipped.
+    fn update_flashing(&mut self, inputs: SignalInputs) -> bool {
+        if self.flash_after.is_zero() {
+            return false;
         }
+        let SignalInputs { waiting_vehicles, cars_in_intersection, vehicle_approaching, queue_lengths, .. } = inputs;
+        let emergency = self.preempted.is_some() || self.requested.is_some();
+
+        if !self.flashing {
+            let idle = waiting_vehicles.iter().all(|&n| n == 0)
+                && queue_lengths.iter().all(|&n| n == 0)
+                && !cars_in_intersection
+                && !vehicle_approaching;
+            if !idle || emergency {
+                self.idle_since = None;
+                return false;
+            }
+            let since = *self.idle_since.get_or_insert(self.clock);
+            if self.clock - since < self.flash_after {
+                return false;
+            }
+            self.force_all_red();
+            self.scrambling = false;
+            self.flashing = true;
+            return true;
+        }
+
+        // Traffic picked back up: timed operation restarts from AllRed once the box is clear
+        if queue_lengths.iter().sum::<u32>() >= FLASH_RESUME_QUEUE || emergency {
+            self.flashing = false;
+            self.idle_since = None;
+            self.force_all_red();
+            return false;
+        }
+
+        if self.current != Direction::AllRed {
+            // The released vehicle has entered the box, or left the line some other way
+            let released_waiting = self.current.approach_index().is_some_and(|i| waiting_vehicles[i] > 0);
+            if cars_in_intersection || !released_waiting {
+                self.force_all_red();
+            }
+        } else if !cars_in_intersection {
+            let start = self.last_green_direction.approach_index().map_or(0, |i| i + 1);
+            let next = (0..Direction::APPROACHES.len())
+                .map(|k| Direction::APPROACHES[(start + k) % Direction::APPROACHES.len()])
+                .find(|dir| dir.approach_index().is_some_and(|i| waiting_vehicles[i] > 0));
+            if let Some(dir) = next {
+                self.start_green(dir);
+            }
+        }
+        true
+    }
+
+    // Sizes each green by the queue it serves instead of the fixed phase durations: min_green plus
+    // per_vehicle for every vehicle queued when it starts, at most max. A zero per_vehicle turns this off.
+    pub fn set_queue_weighted_green(&mut self, per_vehicle: Duration, max: Duration) {
+        self.green_per_vehicle = per_vehicle;
+        self.max_weighted_green = max;
+    }
+
+    pub fn green_per_vehicle(&self) -> Duration {
+        self.green_per_vehicle
     }
 
-    fn next_green_direction(&self) -> Direction {
-        match self.last_green_direction {
+    // Queue-weighted green for the phase led by dir, from the latest queue lengths
+    fn queue_weighted_green(&self, dir: Direction) -> Duration {
+        let queued = dir.approach_index().map_or(0, |i| self.group_totals(self.queue_lengths)[i]);
+        (self.min_green + self.green_per_vehicle * queued).min(self.max_weighted_green).max(self.min_green)
+    }
+
+    // Maximum green of the phase led by dir, queue-weighted for the current green when that is on
+    fn green_duration(&self, dir: Direction) -> Duration {
+        if let Some(weighted) = self.weighted_green.filter(|_| dir == self.current) {
+            return weighted;
+        }
+        self.scheduled_green(dir)
+    }
+
+    // Maximum green from the phase durations; a shared green lasts as long as the longer of the pair
+    fn scheduled_green(&self, dir: Direction) -> Duration {
+        if self.paired {
+            self.phase_duration(dir).max(self.phase_duration(dir.opposite()))
+        } else {
+            self.phase_duration(dir)
+        }
+    }
+
+    // Per-approach values summed over each approach's group when paired
+    fn group_totals(&self, values: [u32; 4]) -> [u32; 4] {
+        if !self.paired {
+            return values;
+        }
+        Direction::APPROACHES.map(|dir| {
+            Direction::APPROACHES
+                .iter()
+                .zip(values)
+                .filter(|(other, _)| **other == dir || **other == dir.opposite())
+                .map(|(_, value)| value)
+                .sum()
+        })
+    }
+
+    fn cycle_successor(&self, dir: Direction) -> Direction {
+        if self.paired {
+            return match dir {
+                Direction::East | Direction::West => Direction::North,
+                _ => Direction::East,
+            };
+        }
+        match dir {
             Direction::North => Direction::South,
             Direction::South => Direction::East,
             Direction::East => Direction::West,
@@ -34,44 +527,598 @@ impl TrafficLightController {
         }
     }
 
-    // Update current green direction if enough time has passed
-    pub fn update(&mut self, waiting_vehicles: u32, cars_in_intersection: bool, vehicles_on_stop_line: bool, _is_congested: bool) {
+    fn next_green_direction(&self, waiting_vehicles: [u32; 4], longest_wait: [u32; 4]) -> Direction {
+        let next = self.cycle_successor(self.last_green_direction);
+        let waiting_vehicles = self.group_totals(waiting_vehicles);
+        let longest_wait = self.group_totals(longest_wait);
+        match self.strategy {
+            SignalStrategy::FixedCycle => next,
+            SignalStrategy::LongestWaiting => self.busiest(next, longest_wait),
+            SignalStrategy::Actuated => match self.tie_break {
+                TieBreak::RoundRobin => self.first_waiting(next, waiting_vehicles).unwrap_or(next),
+                TieBreak::FixedOrder => self.first_waiting(Direction::North, waiting_vehicles).unwrap_or(next),
+                TieBreak::LongestQueue => self.busiest(next, waiting_vehicles),
+            },
+        }
+    }
+
+    // First approach with waiting vehicles, checking in cycle order from `start`
+    fn first_waiting(&self, start: Direction, waiting_vehicles: [u32; 4]) -> Option<Direction> {
+        let mut candidate = start;
+        for _ in 0..self.cycle_len() {
+            if candidate.approach_index().is_some_and(|i| waiting_vehicles[i] > 0) {
+                return Some(candidate);
+            }
+            candidate = self.cycle_successor(candidate);
+        }
+        None
+    }
+
+    // Approach with the highest value; ties go to whichever comes first in the cycle from `start`
+    fn busiest(&self, start: Direction, values: [u32; 4]) -> Direction {
+        let mut best = start;
+        let mut best_value = 0;
+        let mut candidate = start;
+        for _ in 0..self.cycle_len() {
+            let value = candidate.approach_index().map_or(0, |i| values[i]);
+            if value > best_value {
+                best = candidate;
+                best_value = value;
+            }
+            candidate = self.cycle_successor(candidate);
+        }
+        best
+    }
+
+    // Holds every vehicle approach red for `duration` after each `every_cycles` full cycles,
+    // so pedestrians can cross in any direction; 0 turns scrambles off
+    pub fn set_scramble(&mut self, every_cycles: u32, duration: Duration) {
+        self.scramble_every = every_cycles;
+        self.scramble_duration = duration;
+        self.greens_since_scramble = 0;
+        self.scrambling = false;
+    }
+
+    pub fn scramble_every(&self) -> u32 {
+        self.scramble_every
+    }
+
+    // True during a scramble; the phase itself reports as AllRed
+    pub fn is_scramble(&self) -> bool {
+        self.scrambling
+    }
+
+    fn scramble_due(&self) -> bool {
+        self.scramble_every > 0
+            && self.greens_since_scramble >= self.scramble_every * self.cycle_len() as u32
+    }
+
+    // Switch to AllRed immediately; the cycle resumes with the next direction once the box is clear
+    pub fn force_all_red(&mut self) {
+        if self.current != Direction::AllRed {
+            self.last_green_direction = self.current; // Store current green direction
+        }
+        self.current = Direction::AllRed;
+        self.weighted_green = None;
+        self.yellow = false;
+        self.last_switch = self.clock;
+        self.extension = Duration::ZERO;
+        self.last_car_cleared_time = None;
+    }
+
+    // Manual override: the next switch goes to dir instead of the automatic choice,
+    // still passing through AllRed first if the box is occupied
+    pub fn request_green(&mut self, dir: Direction) {
+        if dir.approach_index().is_some() {
+            self.requested = Some(dir);
+        }
+    }
+
+    // Emergency preemption: like request_green, but always through AllRed and without waiting
+    // for the amber, and the green is then held until clear_preemption
+    pub fn preempt(&mut self, dir: Direction) {
+        if dir.approach_index().is_some() {
+            self.preempted = Some(dir);
+        }
+    }
+
+    // Resumes normal cycling from the preempted green
+    pub fn clear_preemption(&mut self) {
+        self.preempted = None;
+    }
+
+    pub fn preempted(&self) -> Option<Direction> {
+        self.preempted
+    }
+
+    fn start_green(&mut self, dir: Direction) {
+        self.awaiting_start = false;
+        self.greens_since_scramble += 1;
+        self.scrambling = false;
+        self.last_green_direction = dir;
+        self.current = dir;
+        self.weighted_green = (!self.green_per_vehicle.is_zero()).then(|| self.queue_weighted_green(dir));
+        self.yellow = false;
+        self.last_switch = self.clock;
+        self.extension = Duration::ZERO;
+        self.last_car_cleared_time = None;
+    }
+
+    // Serves a pending preemption or request_green; returns false when there is none to handle
+    fn handle_request(&mut self, cars_in_intersection: bool, vehicles_on_stop_line: bool) -> bool {
+        let Some(requested) = self.preempted.or(self.requested) else { return false };
+        if self.is_green(requested) {
+            if self.preempted.is_none() {
+                self.requested = None;
+                return false;
+            }
+            return true; // Hold the preempted green
+        }
+        if self.preempted.is_some() && self.current != Direction::AllRed {
+            self.force_all_red();
+            return true;
+        }
+        if self.yellow {
+            return false; // Let the amber run out; the request is served from the AllRed after it
+        }
+
+        if self.current == Direction::AllRed {
+            if !cars_in_intersection {
+                self.start_green(requested);
+                self.requested = None;
+            }
+        } else if cars_in_intersection || vehicles_on_stop_line {
+            self.force_all_red();
+        } else {
+            self.start_green(requested);
+            self.requested = None;
+        }
+        true
+    }
+
+    // Advance logical time by dt and update the current green direction if enough time has passed.
+    // Inputs by rule: waiting_vehicles and vehicle_approaching drive Rule 1 (gap-out) and the gap
+    // extension, and pick the next green; cars_in_intersection and vehicles_on_stop_line drive
+    // Rule 3 (AllRed instead of a direct switch) and hold AllRed until the box is empty;
+    // longest_wait only matters under LongestWaiting. Rule 2 (max phase time) needs only dt.
+    pub fn update(&mut self, dt: Duration, inputs: SignalInputs) {
+        // A change made since the last update, e.g. force_all_red, counts from this update
+        self.record_phase(inputs.waiting_vehicles);
+        self.advance(dt, inputs);
+        self.record_phase(inputs.waiting_vehicles);
+    }
+
+    fn advance(&mut self, dt: Duration, inputs: SignalInputs) {
+        self.clock += dt;
+        let SignalInputs { waiting_vehicles, cars_in_intersection, vehicles_on_stop_line, vehicle_approaching, longest_wait, queue_lengths } = inputs;
+        self.queue_lengths = queue_lengths;
+
+        if self.update_flashing(inputs) {
+            return;
+        }
+
+        if self.handle_request(cars_in_intersection, vehicles_on_stop_line) {
+            return;
+        }
+
+        // Amber always ends in AllRed, which starts the next green as soon as the box is clear
+        if self.yellow {
+            if self.phase_elapsed() >= self.yellow_duration {
+                let scramble = self.scramble_due();
+                self.force_all_red();
+                if scramble {
+                    self.scrambling = true;
+                    self.greens_since_scramble = 0;
+                }
+            }
+            return;
+        }
+
         // Rule 1: If there are no cars waiting to cross the intersection in the desired direction in NO_CARS_DELAY value switch to the next phase
-        let no_cars_waiting_for_current_green = waiting_vehicles == 0;
+        let waiting_for_current: u32 = Direction::APPROACHES
+            .iter()
+            .zip(waiting_vehicles)
+            .filter(|(dir, _)| self.serves(**dir))
+            .map(|(_, waiting)| waiting)
+            .sum();
+        let no_cars_waiting_for_current_green = waiting_for_current == 0;
         if no_cars_waiting_for_current_green && self.last_car_cleared_time.is_none() {
-            self.last_car_cleared_time = Some(Instant::now());
+            self.last_car_cleared_time = Some(self.clock);
         } else if !no_cars_waiting_for_current_green {
             self.last_car_cleared_time = None;
         }
 
-        let time_since_last_car_cleared = self.last_car_cleared_time.map_or(Duration::MAX, |t| t.elapsed());
-        let should_switch_due_to_no_cars = no_cars_waiting_for_current_green && time_since_last_car_cleared >= NO_CARS_DELAY;
+        let time_since_last_car_cleared = self.last_car_cleared_time.map_or(Duration::MAX, |t| self.clock - t);
+        let should_switch_due_to_no_cars = self.actuated_timing()
+            && no_cars_waiting_for_current_green
+            && !vehicle_approaching
+            && time_since_last_car_cleared >= NO_CARS_DELAY
+            && self.phase_elapsed() >= self.min_green;
 
         // Rule 2: Use max time for phase const
-        let max_phase_duration_reached = self.last_switch.elapsed() >= self.max_phase_duration;
+        let mut max_phase_duration_reached = self.phase_elapsed() >= self.green_duration(self.current) + self.extension;
+
+        // Gap extension: hold the green a little longer for a vehicle that would just miss it
+        if max_phase_duration_reached
+            && self.actuated_timing()
+            && vehicle_approaching
+            && self.extension + GAP_EXTENSION <= MAX_EXTENSION
+        {
+            self.extension += GAP_EXTENSION;
+            max_phase_duration_reached = false;
+        }
 
         let should_switch = should_switch_due_to_no_cars || max_phase_duration_reached;
 
         if self.current == Direction::AllRed {
-            if !cars_in_intersection {
-                self.last_green_direction = self.next_green_direction(); // Update last_green_direction before setting current
-                self.current = self.last_green_direction;
-                self.last_switch = Instant::now();
-                self.last_car_cleared_time = None;
+            let scramble_over = !self.scrambling || self.phase_elapsed() >= self.scramble_duration;
+            let started = !self.awaiting_start
+                || match self.strategy {
+                    SignalStrategy::FixedCycle => self.phase_elapsed() >= STARTUP_DELAY,
+                    _ => waiting_vehicles.iter().any(|&n| n > 0),
+                };
+            if !cars_in_intersection && scramble_over && started {
+                self.start_green(self.next_green_direction(waiting_vehicles, longest_wait));
             }
         } else if should_switch {
-            if cars_in_intersection || vehicles_on_stop_line {
+            if !self.yellow_duration.is_zero() {
+                self.yellow = true;
+                self.last_switch = self.clock;
+                self.extension = Duration::ZERO;
+            } else if self.scramble_due() {
+                // The scramble starts like a clearance AllRed and then holds for scramble_duration
+                self.force_all_red();
+                self.scrambling = true;
+                self.greens_since_scramble = 0;
+            } else if cars_in_intersection || vehicles_on_stop_line {
                 // Rule 3: If its time to switch to the next phase but there are cars on the intersection switch to AllRed.
-                self.last_green_direction = self.current; // Store current green direction
-                self.current = Direction::AllRed;
-                self.last_switch = Instant::now();
-                self.last_car_cleared_time = None;
+                self.force_all_red();
             } else {
                 self.last_green_direction = self.current; // Store current green direction
-                self.current = self.next_green_direction();
-                self.last_switch = Instant::now();
-                self.last_car_cleared_time = None;
+                self.start_green(self.next_green_direction(waiting_vehicles, longest_wait));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: Duration = Duration::from_millis(50);
+
+    // Drives a controller in fixed time steps with scripted inputs and records every phase change,
+    // so signal logic can be exercised without a World
+    struct ControllerHarness {
+        controller: TrafficLightController,
+        elapsed: Duration,
+        transitions: Vec<(Duration, Phase)>,
+    }
+
+    impl ControllerHarness {
+        fn new(controller: TrafficLightController) -> Self {
+            Self {
+                controller,
+                elapsed: Duration::ZERO,
+                transitions: Vec::new(),
+            }
+        }
+
+        // Run `steps` updates of STEP with the same inputs
+        fn advance(&mut self, steps: u32, inputs: SignalInputs) {
+            for _ in 0..steps {
+                let before = self.controller.phase();
+                self.controller.update(STEP, inputs);
+                self.elapsed += STEP;
+                if self.controller.phase() != before {
+                    self.transitions.push((self.elapsed, self.controller.phase()));
+                }
             }
         }
+
+        // Time since the harness started at which phase was first entered
+        fn entered(&self, phase: Phase) -> Option<Duration> {
+            self.transitions.iter().find(|(_, p)| *p == phase).map(|(t, _)| *t)
+        }
+    }
+
+    fn waiting(counts: [u32; 4]) -> SignalInputs {
+        SignalInputs { waiting_vehicles: counts, queue_lengths: counts, ..SignalInputs::default() }
+    }
+
+    fn occupied(counts: [u32; 4]) -> SignalInputs {
+        SignalInputs { cars_in_intersection: true, ..waiting(counts) }
+    }
+
+    #[test]
+    fn no_traffic_switches_after_min_green() {
+        let mut harness = ControllerHarness::new(TrafficLightController::new());
+        harness.advance(60, SignalInputs::default());
+        assert_eq!(
+            harness.transitions[..3],
+            [
+                (MIN_GREEN, Phase::Yellow(Direction::North)),
+                (MIN_GREEN + YELLOW_DURATION, Phase::AllRed),
+                (MIN_GREEN + YELLOW_DURATION + STEP, Phase::Green(Direction::South)),
+            ]
+        );
+    }
+
+    #[test]
+    fn heavy_traffic_holds_green_for_max_phase() {
+        let mut harness = ControllerHarness::new(TrafficLightController::new());
+        harness.advance(100, waiting([5; 4]));
+        assert_eq!(harness.transitions[0], (MAX_PHASE_DURATION, Phase::Yellow(Direction::North)));
+    }
+
+    #[test]
+    fn occupied_box_inserts_all_red() {
+        let mut controller = TrafficLightController::new();
+        controller.set_yellow_duration(Duration::ZERO);
+        let mut clear = ControllerHarness::new(controller);
+        clear.advance(70, waiting([5; 4]));
+        assert_eq!(clear.transitions[0], (MAX_PHASE_DURATION, Phase::Green(Direction::South)));
+
+        let mut controller = TrafficLightController::new();
+        controller.set_yellow_duration(Duration::ZERO);
+        let mut blocked = ControllerHarness::new(controller);
+        blocked.advance(70, occupied([5; 4]));
+        assert_eq!(blocked.transitions[0], (MAX_PHASE_DURATION, Phase::AllRed));
+    }
+
+    #[test]
+    fn without_adaptation_every_green_lasts_its_phase_duration() {
+        for counts in [[0; 4], [9; 4], [0, 9, 0, 3]] {
+            let mut controller = TrafficLightController::new();
+            controller.set_adaptive_timing(false);
+            let mut harness = ControllerHarness::new(controller);
+            harness.advance(200, waiting(counts));
+            assert_eq!(harness.entered(Phase::Yellow(Direction::North)), Some(MAX_PHASE_DURATION), "{:?}", counts);
+            let south = harness.entered(Phase::Green(Direction::South)).unwrap();
+            assert_eq!(harness.entered(Phase::Yellow(Direction::South)), Some(south + MAX_PHASE_DURATION), "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn only_actuated_greens_end_early() {
+        assert_eq!(TrafficLightController::new().strategy(), SignalStrategy::Actuated);
+
+        let mut fixed = ControllerHarness::new(TrafficLightController::with_strategy(SignalStrategy::FixedCycle));
+        fixed.advance(100, SignalInputs::default());
+        assert_eq!(fixed.entered(Phase::Yellow(Direction::North)), Some(MAX_PHASE_DURATION));
+
+        let mut actuated = ControllerHarness::new(TrafficLightController::with_strategy(SignalStrategy::Actuated));
+        actuated.advance(100, SignalInputs::default());
+        assert_eq!(actuated.entered(Phase::Yellow(Direction::North)), Some(MIN_GREEN));
+    }
+
+    #[test]
+    fn request_green_switches_to_the_requested_approach() {
+        let mut harness = ControllerHarness::new(TrafficLightController::new());
+        harness.controller.request_green(Direction::East);
+        harness.advance(1, waiting([3, 0, 0, 0]));
+        assert_eq!(harness.transitions, [(STEP, Phase::Green(Direction::East))]);
+
+        // An occupied box is cleared through AllRed first
+        let mut harness = ControllerHarness::new(TrafficLightController::new());
+        harness.controller.request_green(Direction::East);
+        harness.advance(10, occupied([3, 0, 0, 0]));
+        harness.advance(1, waiting([3, 0, 0, 0]));
+        assert_eq!(harness.transitions, [(STEP, Phase::AllRed), (STEP * 11, Phase::Green(Direction::East))]);
+    }
+
+    #[test]
+    fn plan_lists_every_phase_of_a_fixed_cycle() {
+        let mut controller = TrafficLightController::new();
+        controller.set_max_phase_duration(Duration::from_secs(3));
+        let plan = controller.plan();
+        let expected: Vec<(Phase, Duration)> = Direction::APPROACHES
+            .into_iter()
+            .flat_map(|dir| {
+                [
+                    (Phase::Green(dir), Duration::from_secs(3)),
+                    (Phase::Yellow(dir), YELLOW_DURATION),
+                    (Phase::AllRed, Duration::ZERO),
+                ]
+            })
+            .collect();
+        assert_eq!(plan, expected);
+        assert_eq!(plan.iter().map(|(_, d)| *d).sum::<Duration>(), Duration::from_secs(16));
+    }
+
+    #[test]
+    fn approaching_vehicle_extends_the_green() {
+        let queue = waiting([1, 0, 0, 0]);
+        let approaching = SignalInputs { vehicle_approaching: true, ..queue };
+
+        let mut harness = ControllerHarness::new(TrafficLightController::new());
+        harness.advance(100, queue);
+        assert_eq!(harness.entered(Phase::Yellow(Direction::North)), Some(MAX_PHASE_DURATION));
+
+        // Still green when the late arrival reaches the line, then one gap extension later it ends
+        let mut harness = ControllerHarness::new(TrafficLightController::new());
+        harness.advance(58, queue);
+        harness.advance(6, approaching);
+        assert_eq!(harness.controller.phase(), Phase::Green(Direction::North));
+        harness.advance(40, queue);
+        assert_eq!(harness.entered(Phase::Yellow(Direction::North)), Some(MAX_PHASE_DURATION + GAP_EXTENSION));
+
+        // Extensions stop at MAX_EXTENSION however long vehicles keep arriving
+        let mut harness = ControllerHarness::new(TrafficLightController::new());
+        harness.advance(150, approaching);
+        assert_eq!(harness.entered(Phase::Yellow(Direction::North)), Some(MAX_PHASE_DURATION + MAX_EXTENSION));
+    }
+
+    #[test]
+    fn signal_states_light_only_the_served_approach() {
+        let mut harness = ControllerHarness::new(TrafficLightController::new());
+        assert_eq!(harness.controller.phase(), Phase::Green(Direction::North));
+        let states: Vec<_> = harness.controller.signal_states().collect();
+        assert_eq!(
+            states,
+            [
+                (Direction::North, SignalColor::Green),
+                (Direction::South, SignalColor::Red),
+                (Direction::East, SignalColor::Red),
+                (Direction::West, SignalColor::Red),
+            ]
+        );
+
+        harness.advance(61, waiting([1, 0, 0, 0]));
+        assert_eq!(harness.controller.phase(), Phase::Yellow(Direction::North));
+        let yellow: Vec<_> = harness.controller.signal_states().filter(|(_, c)| *c != SignalColor::Red).collect();
+        assert_eq!(yellow, [(Direction::North, SignalColor::Yellow)]);
+    }
+
+    #[test]
+    fn longest_waiting_serves_allred_arrivals_in_arrival_order() {
+        let mut harness = ControllerHarness::new(TrafficLightController::with_strategy(SignalStrategy::LongestWaiting));
+        harness.controller.force_all_red();
+        let arrivals = |waiting: [u32; 4], longest_wait: [u32; 4], cars_in_intersection: bool| SignalInputs {
+            waiting_vehicles: waiting,
+            queue_lengths: waiting,
+            longest_wait,
+            cars_in_intersection,
+            ..SignalInputs::default()
+        };
+
+        // East arrives while the box is still occupied, West later with a longer queue
+        harness.advance(10, arrivals([0, 0, 1, 0], [0, 0, 30, 0], true));
+        harness.advance(10, arrivals([0, 0, 1, 3], [0, 0, 40, 10], true));
+        assert_eq!(harness.controller.phase(), Phase::AllRed);
+
+        harness.advance(1, arrivals([0, 0, 1, 3], [0, 0, 41, 11], false));
+        assert_eq!(harness.controller.phase(), Phase::Green(Direction::East));
+
+        // Once East has gone, West is next even though South comes first in the cycle
+        harness.advance(60, arrivals([0, 0, 0, 3], [0, 0, 0, 70], false));
+        assert_eq!(harness.controller.phase(), Phase::Green(Direction::West));
+    }
+
+    // Actuated controller leaving a North green through AllRed, with the given tie-break
+    fn clearing_after_north(tie_break: TieBreak) -> ControllerHarness {
+        let mut controller = TrafficLightController::with_strategy(SignalStrategy::Actuated);
+        controller.set_tie_break(tie_break);
+        controller.force_all_red();
+        ControllerHarness::new(controller)
+    }
+
+    fn greens(harness: &ControllerHarness) -> Vec<Direction> {
+        harness
+            .transitions
+            .iter()
+            .filter_map(|(_, phase)| match phase {
+                Phase::Green(dir) => Some(*dir),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_robin_serves_equal_queues_in_cycle_order() {
+        let mut harness = clearing_after_north(TieBreak::RoundRobin);
+        harness.advance(400, waiting([2, 2, 2, 2]));
+        assert_eq!(greens(&harness)[..5], [Direction::South, Direction::East, Direction::West, Direction::North, Direction::South]);
+    }
+
+    #[test]
+    fn longest_queue_serves_the_bigger_queue_first() {
+        let mut harness = clearing_after_north(TieBreak::LongestQueue);
+        harness.advance(1, waiting([0, 1, 3, 0]));
+        assert_eq!(harness.controller.phase(), Phase::Green(Direction::East));
+
+        // Equal queues fall back to cycle order from the last green
+        let mut harness = clearing_after_north(TieBreak::LongestQueue);
+        harness.advance(1, waiting([0, 2, 0, 2]));
+        assert_eq!(harness.controller.phase(), Phase::Green(Direction::South));
+    }
+
+    #[test]
+    fn fixed_order_serves_the_first_waiting_from_north() {
+        let mut harness = clearing_after_north(TieBreak::FixedOrder);
+        harness.advance(1, waiting([0, 0, 1, 1]));
+        assert_eq!(harness.controller.phase(), Phase::Green(Direction::East));
+    }
+
+    fn starting_in_all_red(strategy: SignalStrategy) -> ControllerHarness {
+        let mut controller = TrafficLightController::with_strategy(strategy);
+        controller.set_start_phase(Direction::AllRed);
+        ControllerHarness::new(controller)
+    }
+
+    #[test]
+    fn all_red_start_waits_for_the_first_arrival_when_actuated() {
+        let mut harness = starting_in_all_red(SignalStrategy::Actuated);
+        harness.advance(200, SignalInputs::default());
+        assert_eq!(harness.controller.phase(), Phase::AllRed);
+        assert!(harness.transitions.is_empty());
+
+        harness.advance(1, waiting([0, 0, 1, 0]));
+        assert_eq!(harness.transitions, [(STEP * 201, Phase::Green(Direction::East))]);
+    }
+
+    #[test]
+    fn all_red_start_cycles_after_the_startup_delay_when_fixed() {
+        let mut harness = starting_in_all_red(SignalStrategy::FixedCycle);
+        harness.advance(100, SignalInputs::default());
+        let (at, phase) = harness.transitions[0];
+        assert_eq!(at, STARTUP_DELAY);
+        assert!(matches!(phase, Phase::Green(_)), "{:?}", phase);
+    }
+
+    #[test]
+    fn queue_weighting_gives_a_loaded_approach_a_longer_green() {
+        let green_for_queue = |queued: u32| {
+            let mut harness = clearing_after_north(TieBreak::RoundRobin);
+            harness.controller.set_queue_weighted_green(Duration::from_millis(500), Duration::from_secs(10));
+            harness.advance(300, waiting([0, queued, 0, 0]));
+            let start = harness.entered(Phase::Green(Direction::South)).unwrap();
+            harness.entered(Phase::Yellow(Direction::South)).unwrap() - start
+        };
+        let (loaded, light) = (green_for_queue(8), green_for_queue(1));
+        assert_eq!(loaded, MIN_GREEN + Duration::from_secs(4));
+        assert_eq!(light, MIN_GREEN + Duration::from_millis(500));
+        assert!(loaded > light);
+    }
+
+    #[test]
+    fn phase_history_keeps_the_latest_changes_up_to_its_cap() {
+        let mut controller = TrafficLightController::new();
+        controller.set_phase_history(3);
+        let mut harness = ControllerHarness::new(controller);
+        harness.advance(200, waiting([1, 1, 1, 1]));
+        assert!(harness.transitions.len() > 3);
+
+        let history: Vec<(Phase, Duration)> =
+            harness.controller.phase_history().iter().map(|event| (event.phase, event.started_at)).collect();
+        let latest: Vec<(Phase, Duration)> =
+            harness.transitions[harness.transitions.len() - 3..].iter().map(|&(t, phase)| (phase, t)).collect();
+        assert_eq!(history, latest);
+    }
+
+    #[test]
+    fn empty_approach_switches_after_no_cars_delay() {
+        let mut controller = TrafficLightController::new();
+        controller.set_min_green(Duration::ZERO);
+        let mut harness = ControllerHarness::new(controller);
+        harness.advance(10, waiting([1, 0, 0, 0]));
+        // The first update without North cars starts the delay
+        harness.advance(10, SignalInputs::default());
+        assert_eq!(harness.transitions[0], (STEP * 11 + NO_CARS_DELAY, Phase::Yellow(Direction::North)));
+    }
+
+    #[test]
+    fn all_red_holds_until_the_box_clears_then_resumes_the_cycle() {
+        let mut controller = TrafficLightController::new();
+        controller.set_yellow_duration(Duration::ZERO);
+        let mut harness = ControllerHarness::new(controller);
+        harness.advance(200, occupied([5; 4]));
+        assert_eq!(harness.transitions, [(MAX_PHASE_DURATION, Phase::AllRed)]);
+
+        harness.advance(1, waiting([5; 4]));
+        assert_eq!(harness.transitions[1], (STEP * 201, Phase::Green(Direction::South)));
+        // With a clear box and no amber the next green follows directly
+        harness.advance(100, waiting([5; 4]));
+        assert_eq!(harness.transitions[2], (STEP * 201 + MAX_PHASE_DURATION, Phase::Green(Direction::East)));
     }
 }
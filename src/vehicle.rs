@@ -1,128 +1,749 @@
-use crate::{
-    Direction, EASTBOUND_LANE_Y, INTERSECTION_X_END, INTERSECTION_X_START, INTERSECTION_Y_END,
-    INTERSECTION_Y_START, NORTHBOUND_LANE_X, SOUTHBOUND_LANE_X, Turn, VEHICLE_SIZE,
-    WESTBOUND_LANE_Y, WINDOW_HEIGHT, WINDOW_WIDTH,
-};
+use crate::config::Config;
+use crate::{Direction, PositionMode, Turn, VEHICLE_SIZE};
+
+// Body type of a vehicle. Every kind keeps its front bumper where a VEHICLE_SIZE car's would be,
+// so all of them follow the same paths and stop on the same lines; only the body behind differs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VehicleKind {
+    #[default]
+    Car,
+    Truck,
+    Motorcycle,
+}
+
+const TRUCK_SPEED_FACTOR: f32 = 0.6; // Share of the cruise speed a truck tops out at
+
+impl VehicleKind {
+    pub const ALL: [VehicleKind; 3] = [VehicleKind::Car, VehicleKind::Truck, VehicleKind::Motorcycle];
+
+    // Along the direction of travel
+    pub const fn length(self) -> u32 {
+        match self {
+            VehicleKind::Car => VEHICLE_SIZE,
+            VehicleKind::Truck => 36,
+            VehicleKind::Motorcycle => 12,
+        }
+    }
+
+    // Across the lane; never more than VEHICLE_SIZE, which is what the lanes are spaced for
+    pub const fn width(self) -> u32 {
+        match self {
+            VehicleKind::Car | VehicleKind::Truck => VEHICLE_SIZE,
+            VehicleKind::Motorcycle => 8,
+        }
+    }
+
+    // Top speed in a world whose cars cruise at cruise_speed
+    pub fn max_speed(self, cruise_speed: f32) -> f32 {
+        match self {
+            VehicleKind::Truck => cruise_speed * TRUCK_SPEED_FACTOR,
+            VehicleKind::Car | VehicleKind::Motorcycle => cruise_speed,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vehicle {
     pub id: u32,
     pub dir: Direction,
     pub turn: Turn,
+    pub kind: VehicleKind,
     pub x: i32,
     pub y: i32,
+    pub(crate) pos: (f32, f32), // Exact position; x and y hold it rounded to whole pixels
     pub path: Vec<(i32, i32)>,
     pub path_index: usize,
+    pub speed: f32,
+    pub max_speed: f32, // Speed this vehicle cruises at when nothing holds it back
+    pub ticks_in_box: u32,
+    pub stalled_ticks: u32,
+    pub line_stop_ticks: u32, // Ticks spent standing at the stop line on this crossing
+    pub stop_count: u32, // Times the vehicle came to a halt from moving
+    pub wait_ticks: u32, // Ticks spent standing still
+    pub spawned_at: u64, // Tick the vehicle entered the map, restarted on wrap-around
+    pub queued_at: Option<u64>, // Tick the vehicle first stopped before the box, i.e. joined its approach queue
+    pub is_emergency: bool, // Preempts the signal for its approach until it has crossed
+    pub passed: bool, // Left the road; kept only while fading out and ignored by the simulation
+    pub fade_remaining: u32,
+    pub(crate) heading: f32,
+}
+
+impl Vehicle {
+    // Direction of travel in radians using screen coordinates: 0 is east, PI/2 is south
+    pub fn heading(&self) -> f32 {
+        self.heading
+    }
+
+    // Changes the body type, capping the speeds at what the kind reaches in a world cruising at cruise_speed
+    pub fn set_kind(&mut self, kind: VehicleKind, cruise_speed: f32) {
+        self.kind = kind;
+        self.max_speed = kind.max_speed(cruise_speed);
+        self.speed = self.speed.min(self.max_speed);
+    }
+
+    // Rectangle (x, y, width, height) kept clear of other vehicles: the VEHICLE_SIZE square at
+    // (x, y) and, for a kind longer than that, the square its tail is in, length - VEHICLE_SIZE back
+    // along the route. The tail trails along the path, so it follows the vehicle around a turn
+    // instead of swinging into the next lane.
+    pub fn envelope(&self) -> (i32, i32, u32, u32) {
+        let size = VEHICLE_SIZE as i32;
+        let back = self.kind.length().saturating_sub(VEHICLE_SIZE);
+        if back == 0 {
+            return (self.x, self.y, VEHICLE_SIZE, VEHICLE_SIZE);
+        }
+        let tail = self.position_behind(back as f32);
+        let (left, top) = (self.x.min(tail.0), self.y.min(tail.1));
+        let (right, bottom) = (self.x.max(tail.0) + size, self.y.max(tail.1) + size);
+        (left, top, (right - left) as u32, (bottom - top) as u32)
+    }
+
+    // Rectangle the body covers, always inside the envelope. A kind shorter than VEHICLE_SIZE sits at
+    // the front of its square, centred across it and laid along the axis it is mostly heading on;
+    // longer kinds are VEHICLE_SIZE wide and fill the envelope.
+    pub fn footprint(&self) -> (i32, i32, u32, u32) {
+        let (length, width) = (self.kind.length(), self.kind.width());
+        if length > VEHICLE_SIZE {
+            return self.envelope();
+        }
+        let size = VEHICLE_SIZE as i32;
+        let inset = (size - width as i32) / 2;
+        let (sin, cos) = self.heading.sin_cos();
+        if cos.abs() >= sin.abs() {
+            let x = if cos > 0.0 { self.x + size - length as i32 } else { self.x };
+            (x, self.y + inset, length, width)
+        } else {
+            let y = if sin > 0.0 { self.y + size - length as i32 } else { self.y };
+            (self.x + inset, y, width, length)
+        }
+    }
+
+    // True while any part of the vehicle overlaps the intersection box
+    pub fn in_intersection(&self, config: &Config) -> bool {
+        let (x_start, y_start) = (config.intersection_x_start(), config.intersection_y_start());
+        rects_overlap(self.envelope(), (x_start as i32, y_start as i32, config.road_width, config.road_width))
+    }
+
+    // True when the two bodies intersect. Bodies lie within their envelopes, which are cheaper to
+    // compare and rule out almost every pair.
+    pub fn overlaps(&self, other: &Vehicle) -> bool {
+        rects_overlap(self.envelope(), other.envelope()) && rects_overlap(self.footprint(), other.footprint())
+    }
+
+    // True when driving `distance` further along the route would put this vehicle on `other`
+    pub fn closing_on(&self, other: &Vehicle, distance: f32) -> bool {
+        let (x, y) = self.position_along_path(distance);
+        rects_overlap((x, y, VEHICLE_SIZE, VEHICLE_SIZE), other.envelope())
+    }
+
+    // Screen edge the vehicle has driven out through, or None while it is still on screen
+    pub fn exit_edge(&self, config: &Config) -> Option<Direction> {
+        off_screen_edge(config, (self.x, self.y))
+    }
+
+    // Edge the vehicle has reached on its way out, used to retire it while still visible
+    pub fn edge_reached(&self, config: &Config) -> Option<Direction> {
+        if self.path_index < 2 {
+            return None; // Still approaching the intersection
+        }
+        if self.y <= 0 {
+            Some(Direction::North)
+        } else if self.y + VEHICLE_SIZE as i32 >= config.window_height as i32 {
+            Some(Direction::South)
+        } else if self.x <= 0 {
+            Some(Direction::West)
+        } else if self.x + VEHICLE_SIZE as i32 >= config.window_width as i32 {
+            Some(Direction::East)
+        } else {
+            None
+        }
+    }
+
+    // Puts the vehicle back at the start of a fresh route, clearing its per-crossing statistics
+    pub fn restart(&mut self, dir: Direction, path: Vec<(i32, i32)>, tick: u64) {
+        let start = path[0];
+        self.heading = segment_heading(start, path[1]);
+        self.dir = dir;
+        self.x = start.0;
+        self.y = start.1;
+        self.pos = (start.0 as f32, start.1 as f32);
+        self.path = path;
+        self.path_index = 0;
+        self.ticks_in_box = 0;
+        self.stalled_ticks = 0;
+        self.line_stop_ticks = 0;
+        self.stop_count = 0;
+        self.wait_ticks = 0;
+        self.spawned_at = tick;
+        self.queued_at = None;
+    }
+
+    // Brightness from 1.0 (active) down towards 0.0 as a passed vehicle fades out
+    pub fn fade_level(&self, fade_ticks: u32) -> f32 {
+        if !self.passed || fade_ticks == 0 {
+            return 1.0;
+        }
+        self.fade_remaining as f32 / fade_ticks as f32
+    }
+
+    // Moves up to `speed` pixels towards the next waypoint, never past it, so braking
+    // vehicles stop exactly on the line. Reaching a waypoint advances path_index on the next tick;
+    // only the stop line costs that tick, so the light is checked there before entering the box.
+    pub fn advance(&mut self, mode: PositionMode) {
+        if self.path_index >= self.path.len() - 1 {
+            return;
+        }
+        let mut target = self.path[self.path_index + 1];
+        let arrived = match mode {
+            PositionMode::Integer => (self.x, self.y) == target,
+            PositionMode::Float => self.pos == (target.0 as f32, target.1 as f32),
+        };
+        if arrived {
+            self.path_index += 1;
+            if self.path_index <= 1 || self.path_index >= self.path.len() - 1 {
+                return;
+            }
+            target = self.path[self.path_index + 1];
+        }
+
+        match mode {
+            PositionMode::Integer => self.step_integer(target),
+            PositionMode::Float => self.step_float(target),
+        }
+    }
+
+    fn step_integer(&mut self, target: (i32, i32)) {
+        let dx = target.0 - self.x;
+        let dy = target.1 - self.y;
+        let step = self.speed as i32;
+        let from = (self.x, self.y);
+        // x first, then whatever is left of the step on y, so diagonal arc segments still cover `step` pixels
+        let step_x = step.min(dx.abs());
+        self.x += dx.signum() * step_x;
+        self.y += dy.signum() * (step - step_x).min(dy.abs());
+        if (self.x, self.y) != from {
+            self.heading = segment_heading(from, (self.x, self.y));
+        }
+        self.pos = (self.x as f32, self.y as f32);
+    }
+
+    fn step_float(&mut self, target: (i32, i32)) {
+        let dx = target.0 as f32 - self.pos.0;
+        let dy = target.1 as f32 - self.pos.1;
+        let distance = dx.hypot(dy);
+        if distance <= self.speed {
+            self.pos = (target.0 as f32, target.1 as f32);
+        } else {
+            self.pos.0 += dx / distance * self.speed;
+            self.pos.1 += dy / distance * self.speed;
+        }
+        if self.speed > 0.0 {
+            self.heading = dy.atan2(dx);
+        }
+        self.x = self.pos.0.round() as i32;
+        self.y = self.pos.1.round() as i32;
+    }
+
+    // Top-left corner after driving `distance` further along the remaining route, or the end
+    // of the route if it is shorter
+    pub fn position_along_path(&self, distance: f32) -> (i32, i32) {
+        let mut pos = self.pos;
+        let mut remaining = distance;
+        for &(wx, wy) in self.path.iter().skip(self.path_index + 1) {
+            let (dx, dy) = (wx as f32 - pos.0, wy as f32 - pos.1);
+            let length = (dx * dx + dy * dy).sqrt();
+            if length >= remaining {
+                if length > 0.0 {
+                    pos = (pos.0 + dx / length * remaining, pos.1 + dy / length * remaining);
+                }
+                return (pos.0.round() as i32, pos.1.round() as i32);
+            }
+            pos = (wx as f32, wy as f32);
+            remaining -= length;
+        }
+        (pos.0.round() as i32, pos.1.round() as i32)
+    }
+
+    // Top-left corner `distance` pixels back along the route driven so far, continuing straight
+    // back from the first waypoint for a vehicle that has only just entered
+    pub fn position_behind(&self, distance: f32) -> (i32, i32) {
+        let mut pos = self.pos;
+        let mut remaining = distance;
+        for &(wx, wy) in self.path[..=self.path_index.min(self.path.len() - 1)].iter().rev() {
+            let (dx, dy) = (wx as f32 - pos.0, wy as f32 - pos.1);
+            let length = (dx * dx + dy * dy).sqrt();
+            if length >= remaining {
+                if length > 0.0 {
+                    pos = (pos.0 + dx / length * remaining, pos.1 + dy / length * remaining);
+                }
+                return (pos.0.round() as i32, pos.1.round() as i32);
+            }
+            pos = (wx as f32, wy as f32);
+            remaining -= length;
+        }
+        let (sin, cos) = match self.path[..] {
+            [first, second, ..] => segment_heading(first, second).sin_cos(),
+            _ => self.heading.sin_cos(),
+        };
+        ((pos.0 - cos * remaining).round() as i32, (pos.1 - sin * remaining).round() as i32)
+    }
+
+    // Multi-line summary of the vehicle's state for the inspector panel
+    pub fn debug_report(&self, config: &Config) -> String {
+        let state = if self.passed {
+            "passed"
+        } else if self.in_intersection(config) {
+            "in intersection"
+        } else if self.speed == 0.0 {
+            "stopped"
+        } else {
+            "moving"
+        };
+        format!(
+            "Vehicle {}\n\
+             Dir: {:?}, turn: {:?}\n\
+             Pos: ({:.1}, {:.1})\n\
+             Path index: {} of {}\n\
+             Next waypoint: {:?}\n\
+             Speed: {:.2} px/tick\n\
+             State: {}\n\
+             Stops: {}, waited {} ticks",
+            self.id,
+            self.dir,
+            self.turn,
+            self.pos.0,
+            self.pos.1,
+            self.path_index,
+            self.path.len() - 1,
+            self.path.get(self.path_index + 1),
+            self.speed,
+            state,
+            self.stop_count,
+            self.wait_ticks,
+        )
+    }
+
+    // Distance left to the stop line waypoint, or None once the vehicle has reached it
+    pub fn distance_to_stop_line(&self) -> Option<i32> {
+        if self.path_index > 0 {
+            return None;
+        }
+        let stop = self.path[1];
+        Some((stop.0 - self.x).abs() + (stop.1 - self.y).abs())
+    }
+}
+
+// True when two (x, y, width, height) rectangles share any area
+pub fn rects_overlap(a: (i32, i32, u32, u32), b: (i32, i32, u32, u32)) -> bool {
+    a.0 < b.0 + b.2 as i32 && b.0 < a.0 + a.2 as i32 && a.1 < b.1 + b.3 as i32 && b.1 < a.1 + a.3 as i32
+}
+
+// Heading in radians of the straight segment from `from` to `to`
+pub fn segment_heading(from: (i32, i32), to: (i32, i32)) -> f32 {
+    ((to.1 - from.1) as f32).atan2((to.0 - from.0) as f32)
+}
+
+// Consecutive waypoint pairs, one per straight segment of the path
+pub fn path_segments(path: &[(i32, i32)]) -> Vec<((i32, i32), (i32, i32))> {
+    path.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+// Path coordinates are the top-left corner of the vehicle square, so a vehicle centred on a
+// lane sits LANE_OFFSET before the lane's centre line. Every path starts just off screen,
+// stops at the stop line (index 1), and ends just off screen on its exit edge.
+// Straight paths keep a box-entry waypoint at index 2 so path_index means the same thing
+// for every turn; it lies on the lane line, so the trajectory stays collinear.
+const LANE_OFFSET: i32 = VEHICLE_SIZE as i32 / 2;
+
+// Lane positions as path coordinates. The outer lane of each direction carries straight traffic
+// and turns towards the curb, and receives every vehicle exiting that way; the inner lane is used
+// only by vehicles waiting to turn across oncoming traffic (see Config::crossing_turn).
+struct Lanes {
+    southbound: i32,
+    southbound_inner: i32,
+    northbound: i32,
+    northbound_inner: i32,
+    westbound: i32,
+    westbound_inner: i32,
+    eastbound: i32,
+    eastbound_inner: i32,
+}
+
+impl Lanes {
+    fn new(config: &Config) -> Self {
+        let shift = config.turn_lane_shift() * config.traffic_side();
+        Self {
+            southbound: config.southbound_lane_x() - LANE_OFFSET - shift,
+            southbound_inner: config.southbound_lane_x() - LANE_OFFSET + shift,
+            northbound: config.northbound_lane_x() - LANE_OFFSET + shift,
+            northbound_inner: config.northbound_lane_x() - LANE_OFFSET - shift,
+            westbound: config.westbound_lane_y() - LANE_OFFSET - shift,
+            westbound_inner: config.westbound_lane_y() - LANE_OFFSET + shift,
+            eastbound: config.eastbound_lane_y() - LANE_OFFSET + shift,
+            eastbound_inner: config.eastbound_lane_y() - LANE_OFFSET - shift,
+        }
+    }
+}
+
+// The two lanes of an approach sit side by side within their half of the road, and the opposing
+// inner lanes share no lateral space, so nothing overlaps even if both approaches were green at once
+pub(crate) fn lanes_fit(config: &Config) -> bool {
+    let lanes = Lanes::new(config);
+    let mut xs = [lanes.southbound, lanes.southbound_inner, lanes.northbound_inner, lanes.northbound];
+    let mut ys = [lanes.westbound, lanes.westbound_inner, lanes.eastbound_inner, lanes.eastbound];
+    xs.sort();
+    ys.sort();
+    let fits = |lanes: &[i32; 4], road_start: u32| {
+        let size = VEHICLE_SIZE as i32;
+        lanes[0] >= road_start as i32
+            && lanes.windows(2).all(|w| w[0] + size <= w[1])
+            && lanes[3] + size <= (road_start + config.road_width) as i32
+    };
+    fits(&xs, config.road_x()) && fits(&ys, config.road_y())
+}
+
+const TURN_RADIUS: i32 = 30; // Turns across oncoming traffic start and end this far from the lane corner
+const CURB_TURN_RADIUS: i32 = 20; // Tighter, as the outer lanes leave under 30 px from stop line to corner
+const TURN_ARC_SEGMENTS: i32 = 6;
+
+// Wide for the turn across oncoming traffic, tight for the turn towards the curb
+fn turn_radius(config: &Config, turn: Turn) -> i32 {
+    if turn == config.crossing_turn() { TURN_RADIUS } else { CURB_TURN_RADIUS }
+}
+
+// Quadratic Bézier from `radius` before `corner` (travelling along `entry`) to `radius` after
+// it (travelling along `exit`), with the corner as control point, sampled into waypoints
+fn turn_arc(corner: (i32, i32), entry: (i32, i32), exit: (i32, i32), radius: i32) -> Vec<(i32, i32)> {
+    let start = (corner.0 - entry.0 * radius, corner.1 - entry.1 * radius);
+    let end = (corner.0 + exit.0 * radius, corner.1 + exit.1 * radius);
+    let bezier = |t: f32, a: i32, c: i32, b: i32| {
+        let u = 1.0 - t;
+        (u * u * a as f32 + 2.0 * u * t * c as f32 + t * t * b as f32).round() as i32
+    };
+    (0..=TURN_ARC_SEGMENTS)
+        .map(|i| {
+            let t = i as f32 / TURN_ARC_SEGMENTS as f32;
+            (bezier(t, start.0, corner.0, end.0), bezier(t, start.1, corner.1, end.1))
+        })
+        .collect()
 }
 
-pub fn generate_path(dir: Direction, turn: Turn) -> Vec<(i32, i32)> {
+// Screen edge beyond which a vehicle with its top-left corner at `point` is entirely off screen
+pub fn off_screen_edge(config: &Config, (x, y): (i32, i32)) -> Option<Direction> {
+    if y <= -(VEHICLE_SIZE as i32) {
+        Some(Direction::North)
+    } else if y >= config.window_height as i32 + VEHICLE_SIZE as i32 {
+        Some(Direction::South)
+    } else if x <= -(VEHICLE_SIZE as i32) {
+        Some(Direction::West)
+    } else if x >= config.window_width as i32 + VEHICLE_SIZE as i32 {
+        Some(Direction::East)
+    } else {
+        None
+    }
+}
+
+// Waypoints for the top-left corner of a vehicle, in window pixels with y growing downwards.
+// `dir` is the edge the vehicle comes from, so North drives southbound, and a turn is seen from
+// the driver's seat: North + Left exits through the East edge. The route starts off screen at
+// `dir`, stops at its stop line (the second waypoint) and ends off screen at dir.exit(turn).
+pub fn generate_path(config: &Config, dir: Direction, turn: Turn) -> Vec<(i32, i32)> {
     match dir {
-        Direction::North => generate_north_path(turn),
-        Direction::South => generate_south_path(turn),
-        Direction::East => generate_east_path(turn),
-        Direction::West => generate_west_path(turn),
-        Direction::AllRed => panic!("Cannot generate path for AllRed direction"),
+        Direction::North => generate_north_path(config, turn),
+        Direction::South => generate_south_path(config, turn),
+        Direction::East => generate_east_path(config, turn),
+        Direction::West => generate_west_path(config, turn),
+        Direction::AllRed => Vec::new(), // Not an approach, so there is no route
     }
 }
 
-fn generate_north_path(turn: Turn) -> Vec<(i32, i32)> {
+fn generate_north_path(config: &Config, turn: Turn) -> Vec<(i32, i32)> {
+    let lanes = Lanes::new(config);
     let mut path = Vec::new();
-    let x = SOUTHBOUND_LANE_X - 10;
+    let x = if turn == config.crossing_turn() { lanes.southbound_inner } else { lanes.southbound };
     path.push((x, -20));
-    path.push((x, INTERSECTION_Y_START as i32 - VEHICLE_SIZE as i32 - 5)); // stopping point
+    path.push((x, config.intersection_y_start() as i32 - VEHICLE_SIZE as i32 - 5)); // stopping point
     match turn {
         Turn::Straight => {
-            path.push((x, INTERSECTION_Y_START as i32 + 6));
-            path.push((x, WINDOW_HEIGHT as i32 + VEHICLE_SIZE as i32));
+            path.push((x, config.intersection_y_start() as i32 + 6));
+            path.push((x, config.window_height as i32 + VEHICLE_SIZE as i32));
         }
         Turn::Left => {
             // Turn left to go East
-            path.push((x, EASTBOUND_LANE_Y - 10));
-            path.push((WINDOW_WIDTH as i32 + VEHICLE_SIZE as i32, EASTBOUND_LANE_Y - 10));
+            path.extend(turn_arc((x, lanes.eastbound), (0, 1), (1, 0), turn_radius(config, turn)));
+            path.push((config.window_width as i32 + VEHICLE_SIZE as i32, lanes.eastbound));
         }
         Turn::Right => {
             // Turn right to go West
-            path.push((x, WESTBOUND_LANE_Y - 10));
-            path.push((-20, WESTBOUND_LANE_Y - 10));
+            path.extend(turn_arc((x, lanes.westbound), (0, 1), (-1, 0), turn_radius(config, turn)));
+            path.push((-(VEHICLE_SIZE as i32), lanes.westbound));
         }
     }
     path
 }
 
-fn generate_south_path(turn: Turn) -> Vec<(i32, i32)> {
+fn generate_south_path(config: &Config, turn: Turn) -> Vec<(i32, i32)> {
+    let lanes = Lanes::new(config);
     let mut path = Vec::new();
-    let x = NORTHBOUND_LANE_X - 10;
-    path.push((x, WINDOW_HEIGHT as i32 + VEHICLE_SIZE as i32));
-    path.push((x, INTERSECTION_Y_END as i32 + 5)); // stopping point
+    let x = if turn == config.crossing_turn() { lanes.northbound_inner } else { lanes.northbound };
+    path.push((x, config.window_height as i32 + VEHICLE_SIZE as i32));
+    path.push((x, config.intersection_y_end() as i32 + 5)); // stopping point
     match turn {
         Turn::Straight => {
-            path.push((x, INTERSECTION_Y_END as i32 - 6));
+            path.push((x, config.intersection_y_end() as i32 - 6));
             path.push((x, -(VEHICLE_SIZE as i32)));
         }
         Turn::Left => {
             // Turn left to go West
-            path.push((x, WESTBOUND_LANE_Y - 10));
-            path.push((-(VEHICLE_SIZE as i32), WESTBOUND_LANE_Y - 10));
+            path.extend(turn_arc((x, lanes.westbound), (0, -1), (-1, 0), turn_radius(config, turn)));
+            path.push((-(VEHICLE_SIZE as i32), lanes.westbound));
         }
         Turn::Right => {
             // Turn right to go East
-            path.push((x, EASTBOUND_LANE_Y - 10));
-            path.push((WINDOW_WIDTH as i32 + VEHICLE_SIZE as i32, EASTBOUND_LANE_Y - 10));
+            path.extend(turn_arc((x, lanes.eastbound), (0, -1), (1, 0), turn_radius(config, turn)));
+            path.push((config.window_width as i32 + VEHICLE_SIZE as i32, lanes.eastbound));
         }
     }
     path
 }
 
-fn generate_east_path(turn: Turn) -> Vec<(i32, i32)> {
+fn generate_east_path(config: &Config, turn: Turn) -> Vec<(i32, i32)> {
+    let lanes = Lanes::new(config);
     let mut path = Vec::new();
-    let y = WESTBOUND_LANE_Y - 10;
-    path.push((WINDOW_WIDTH as i32 + VEHICLE_SIZE as i32, y));
-    path.push((INTERSECTION_X_END as i32 + 5, y)); // stopping point
+    let y = if turn == config.crossing_turn() { lanes.westbound_inner } else { lanes.westbound };
+    path.push((config.window_width as i32 + VEHICLE_SIZE as i32, y));
+    path.push((config.intersection_x_end() as i32 + 5, y)); // stopping point
     match turn {
         Turn::Straight => {
-            path.push((INTERSECTION_X_END as i32 - 6, y));
+            path.push((config.intersection_x_end() as i32 - 6, y));
             path.push((-(VEHICLE_SIZE as i32), y));
         }
         Turn::Left => {
             // Turn left to go South
-            path.push((SOUTHBOUND_LANE_X - 10, y));
-            path.push((
-                SOUTHBOUND_LANE_X - 10,
-                WINDOW_HEIGHT as i32 + VEHICLE_SIZE as i32,
-            ));
+            path.extend(turn_arc((lanes.southbound, y), (-1, 0), (0, 1), turn_radius(config, turn)));
+            path.push((lanes.southbound, config.window_height as i32 + VEHICLE_SIZE as i32));
         }
         Turn::Right => {
             // Turn right to go North
-            path.push((NORTHBOUND_LANE_X - 10, y));
-            path.push((NORTHBOUND_LANE_X - 10, -(VEHICLE_SIZE as i32)));
+            path.extend(turn_arc((lanes.northbound, y), (-1, 0), (0, -1), turn_radius(config, turn)));
+            path.push((lanes.northbound, -(VEHICLE_SIZE as i32)));
         }
     }
     path
 }
 
-fn generate_west_path(turn: Turn) -> Vec<(i32, i32)> {
+fn generate_west_path(config: &Config, turn: Turn) -> Vec<(i32, i32)> {
+    let lanes = Lanes::new(config);
     let mut path = Vec::new();
-    let y = EASTBOUND_LANE_Y - 10;
+    let y = if turn == config.crossing_turn() { lanes.eastbound_inner } else { lanes.eastbound };
     path.push((-(VEHICLE_SIZE as i32), y));
-    path.push((INTERSECTION_X_START as i32 - VEHICLE_SIZE as i32 - 5, y)); // stopping point
+    path.push((config.intersection_x_start() as i32 - VEHICLE_SIZE as i32 - 5, y)); // stopping point
     match turn {
         Turn::Straight => {
-            path.push((INTERSECTION_X_START as i32 + 6, y));
-            path.push((WINDOW_WIDTH as i32 + VEHICLE_SIZE as i32, y));
+            path.push((config.intersection_x_start() as i32 + 6, y));
+            path.push((config.window_width as i32 + VEHICLE_SIZE as i32, y));
         }
         Turn::Left => {
             // Turn left to go North
-            path.push((NORTHBOUND_LANE_X - 10, y));
-            path.push((NORTHBOUND_LANE_X - 10, -(VEHICLE_SIZE as i32)));
+            path.extend(turn_arc((lanes.northbound, y), (1, 0), (0, -1), turn_radius(config, turn)));
+            path.push((lanes.northbound, -(VEHICLE_SIZE as i32)));
         }
         Turn::Right => {
             // Turn right to go South
-            path.push((SOUTHBOUND_LANE_X - 10, y));
-            path.push(
-                (SOUTHBOUND_LANE_X - 10,
-                WINDOW_HEIGHT as i32 + (VEHICLE_SIZE as i32)),
-            );
+            path.extend(turn_arc((lanes.southbound, y), (1, 0), (0, 1), turn_radius(config, turn)));
+            path.push((lanes.southbound, config.window_height as i32 + VEHICLE_SIZE as i32));
         }
     }
     path
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::World;
+    use std::f32::consts::FRAC_PI_2;
+
+    const TURNS: [Turn; 3] = [Turn::Left, Turn::Right, Turn::Straight];
+
+    // Vehicle at the start of an arbitrary path, moving at speed
+    fn vehicle_on(path: Vec<(i32, i32)>, speed: f32) -> Vehicle {
+        let mut world = World::with_seed(0);
+        world.add_vehicle(Direction::North, Turn::Straight, (0.0, 0.0), 0);
+        let mut v = world.vehicles.pop().unwrap();
+        v.restart(Direction::North, path, 0);
+        v.speed = speed;
+        v
+    }
+
+    #[test]
+    fn path_segments_join_consecutive_waypoints() {
+        let config = Config::default();
+        for dir in Direction::APPROACHES {
+            for turn in TURNS {
+                let path = generate_path(&config, dir, turn);
+                let segments = path_segments(&path);
+                assert_eq!(segments.len(), path.len() - 1, "{:?} {:?}", dir, turn);
+                for (segment, pair) in segments.iter().zip(path.windows(2)) {
+                    assert_eq!(*segment, (pair[0], pair[1]));
+                }
+            }
+        }
+        assert!(path_segments(&[(0, 0)]).is_empty());
+    }
+
+    #[test]
+    fn left_turn_heading_swings_from_entry_to_exit_axis() {
+        let mut world = World::with_seed(1);
+        let start = generate_path(world.config(), Direction::North, Turn::Left)[0];
+        world.add_vehicle(Direction::North, Turn::Left, (start.0 as f32, start.1 as f32), 0);
+        let mut v = world.vehicles.pop().unwrap();
+        assert!((v.heading() - FRAC_PI_2).abs() < 1e-3, "enters heading {}", v.heading());
+
+        let mut headings = Vec::new();
+        while v.path_index < v.path.len() - 1 {
+            v.advance(PositionMode::Integer);
+            headings.push(v.heading());
+        }
+        assert!(v.heading().abs() < 1e-3, "leaves heading {}", v.heading());
+        assert!(headings.iter().any(|h| *h > 0.1 && *h < FRAC_PI_2 - 0.1), "never turned gradually");
+    }
+
+    #[test]
+    fn integer_mode_truncates_while_float_mode_reaches_the_waypoint() {
+        let path = vec![(0, 0), (3, 10), (3, 100)];
+
+        // Whole-pixel steps: the speed truncates, and x is covered before y
+        let mut v = vehicle_on(path.clone(), 2.7);
+        let mut positions = Vec::new();
+        for _ in 0..3 {
+            v.advance(PositionMode::Integer);
+            positions.push((v.x, v.y));
+        }
+        assert_eq!(positions, [(2, 0), (3, 1), (3, 3)]);
+
+        // Below a pixel per tick integer stepping never moves, float stepping gets there
+        let mut slow_integer = vehicle_on(path.clone(), 0.5);
+        let mut slow_float = vehicle_on(path, 0.5);
+        for _ in 0..22 {
+            slow_integer.advance(PositionMode::Integer);
+            slow_float.advance(PositionMode::Float);
+        }
+        assert_eq!((slow_integer.x, slow_integer.y), (0, 0));
+        assert_eq!(slow_float.pos, (3.0, 10.0));
+        assert_eq!(slow_float.path_index, 1);
+    }
+
+    #[test]
+    fn vehicle_on_a_waypoint_looks_along_the_following_segment() {
+        let mut v = vehicle_on(vec![(0, 0), (0, 50), (50, 50)], 5.0);
+        // Standing on the waypoint it is heading for, so the vector to it is zero
+        (v.x, v.y, v.pos) = (0, 50, (0.0, 50.0));
+        assert_eq!(v.position_along_path(10.0), (10, 50));
+        let mut ahead = vehicle_on(vec![(25, 50), (100, 50)], 0.0);
+        let mut beside = vehicle_on(vec![(0, 75), (0, 100)], 0.0);
+        assert!(v.closing_on(&ahead, 10.0));
+        assert!(!v.closing_on(&beside, 10.0));
+
+        // At the end of the route there is nowhere further to go, so only what it already touches counts
+        (v.x, v.y, v.pos, v.path_index) = (50, 50, (50.0, 50.0), 1);
+        assert_eq!(v.position_along_path(10.0), (50, 50));
+        (ahead.x, ahead.y) = (65, 50);
+        (beside.x, beside.y) = (75, 50);
+        assert!(v.closing_on(&ahead, 10.0));
+        assert!(!v.closing_on(&beside, 10.0));
+    }
+
+    #[test]
+    fn default_paths_match_the_drawn_layout() {
+        use Direction::{East, North, South, West};
+        use Turn::{Left, Right, Straight};
+        let expected = [
+            (North, Left, vec![(377, -20), (377, 225), (377, 297), (378, 306), (380, 314), (385, 320), (390, 324), (398, 326), (407, 327), (820, 327)]),
+            (North, Right, vec![(353, -20), (353, 225), (353, 233), (352, 239), (351, 244), (348, 248), (344, 251), (339, 252), (333, 253), (-20, 253)]),
+            (North, Straight, vec![(353, -20), (353, 225), (353, 256), (353, 620)]),
+            (South, Left, vec![(403, 620), (403, 355), (403, 283), (402, 274), (400, 266), (396, 261), (390, 256), (382, 254), (373, 253), (-20, 253)]),
+            (South, Right, vec![(427, 620), (427, 355), (427, 347), (428, 341), (429, 336), (432, 332), (436, 329), (441, 328), (447, 327), (820, 327)]),
+            (South, Straight, vec![(427, 620), (427, 355), (427, 344), (427, -20)]),
+            (East, Left, vec![(820, 277), (455, 277), (383, 277), (374, 278), (366, 280), (361, 285), (356, 290), (354, 298), (353, 307), (353, 620)]),
+            (East, Right, vec![(820, 253), (455, 253), (447, 253), (441, 252), (436, 251), (432, 248), (429, 244), (428, 239), (427, 233), (427, -20)]),
+            (East, Straight, vec![(820, 253), (455, 253), (444, 253), (-20, 253)]),
+            (West, Left, vec![(-20, 303), (325, 303), (397, 303), (406, 302), (414, 300), (420, 296), (424, 290), (426, 282), (427, 273), (427, -20)]),
+            (West, Right, vec![(-20, 327), (325, 327), (333, 327), (339, 328), (344, 329), (348, 332), (351, 336), (352, 341), (353, 347), (353, 620)]),
+            (West, Straight, vec![(-20, 327), (325, 327), (356, 327), (820, 327)]),
+        ];
+        let config = Config::default();
+        for (dir, turn, path) in expected {
+            assert_eq!(generate_path(&config, dir, turn), path, "{:?} {:?}", dir, turn);
+        }
+    }
+
+    #[test]
+    fn straight_paths_never_drift_sideways() {
+        let config = Config::default();
+        for dir in Direction::APPROACHES {
+            let path = generate_path(&config, dir, Turn::Straight);
+            let (x0, y0) = path[0];
+            match dir {
+                Direction::North | Direction::South => assert!(path.iter().all(|p| p.0 == x0), "{:?} {:?}", dir, path),
+                _ => assert!(path.iter().all(|p| p.1 == y0), "{:?} {:?}", dir, path),
+            }
+        }
+    }
+
+    #[test]
+    fn debug_report_names_the_vehicle_and_its_progress() {
+        let mut world = World::with_seed(2);
+        let id = world.spawn_vehicle_with_turn(Direction::West, Turn::Left).unwrap();
+        for _ in 0..150 {
+            world.update();
+        }
+        let v = world.vehicles.iter().find(|v| v.id == id).unwrap();
+        assert!(v.path_index > 0);
+        let report = v.debug_report(world.config());
+        assert!(report.contains(&format!("Vehicle {}", id)), "{}", report);
+        assert!(report.contains("Dir: West, turn: Left"), "{}", report);
+        assert!(report.contains(&format!("Path index: {} of {}", v.path_index, v.path.len() - 1)), "{}", report);
+    }
+
+    #[test]
+    fn keep_left_mirrors_the_north_straight_path() {
+        let right = Config::default();
+        let left = Config::default().with_drive_on_left(true);
+        let right_path = generate_path(&right, Direction::North, Turn::Straight);
+        let left_path = generate_path(&left, Direction::North, Turn::Straight);
+        assert_eq!(right_path[0].0, 353);
+        assert_eq!(left_path[0].0, 427);
+        // Reflected about the vertical centre line of the window, with y untouched
+        let mirrored: Vec<(i32, i32)> =
+            right_path.iter().map(|&(x, y)| (right.window_width as i32 - x - VEHICLE_SIZE as i32, y)).collect();
+        assert_eq!(left_path, mirrored);
+    }
+
+    // Waypoints are top-left corners in window pixels, y down. `dir` names the edge a vehicle
+    // enters from, and the stop waypoint leaves its front bumper 5 px short of the box.
+    #[test]
+    fn every_path_runs_edge_to_stop_line_to_exit_edge() {
+        let config = Config::default();
+        let size = VEHICLE_SIZE as i32;
+        let (left, top) = (config.intersection_x_start() as i32, config.intersection_y_start() as i32);
+        let (right, bottom) = (config.intersection_x_end() as i32, config.intersection_y_end() as i32);
+        for dir in Direction::APPROACHES {
+            for turn in TURNS {
+                let path = generate_path(&config, dir, turn);
+                assert_eq!(off_screen_edge(&config, path[0]), Some(dir), "{:?} {:?} start", dir, turn);
+                let (x, y) = path[1];
+                let front_gap = match dir {
+                    Direction::North => top - (y + size),
+                    Direction::South => y - bottom,
+                    Direction::East => x - right,
+                    _ => left - (x + size),
+                };
+                assert_eq!(front_gap, 5, "{:?} {:?} stop line", dir, turn);
+                let last = *path.last().unwrap();
+                assert_eq!(off_screen_edge(&config, last), Some(dir.exit(turn)), "{:?} {:?} exit", dir, turn);
+            }
+        }
+        assert!(generate_path(&config, Direction::AllRed, Turn::Straight).is_empty());
+    }
+}